@@ -0,0 +1,252 @@
+//! Aggregate queries over the table of a derived type, generated as `count_query()`,
+//! `sum_query(column)`, `avg_query`, `min_query`, `max_query` and `group_by(column)`.
+//!
+//! ```ignore
+//! let pages: Option<i64> = BookNote::sum_query(BookNote::columns().pages)
+//!     .filter("book_id = ?")
+//!     .bind(1i64)
+//!     .fetch_one(&pool)
+//!     .await?;
+//! let per_color: Vec<(Option<String>, i64)> =
+//!     BookNote::group_by(BookNote::columns().color).count().fetch_all(&pool).await?;
+//! ```
+//!
+//! `SUM` and `AVG` results have the type the database computes: [`Sum::Output`] and
+//! [`Avg::Output`] of the column type, `Decimal` (feature `rust_decimal`) for the
+//! `NUMERIC`/`DECIMAL` results of postgres and mysql.
+use std::marker::PhantomData;
+
+use sqlx::query::Query;
+use sqlx::{ColumnIndex, Database, Decode, Executor, IntoArguments, Row, Type};
+
+use crate::{Column, Model};
+
+type BoundQuery<'q, DB> = Query<'q, DB, <DB as Database>::Arguments<'q>>;
+type Binder<DB> = Box<dyn for<'q> FnOnce(BoundQuery<'q, DB>) -> BoundQuery<'q, DB> + Send>;
+
+/// Type of `SUM(column)` for a column of type `Self` on `DB`.
+pub trait Sum<DB: Database> {
+    type Output;
+}
+
+/// Type of `AVG(column)` for a column of type `Self` on `DB`.
+pub trait Avg<DB: Database> {
+    type Output;
+}
+
+macro_rules! sum {
+    ($db:ty: $($t:ty),* => $out:ty) => {
+        $(impl Sum<$db> for $t {
+            type Output = $out;
+        })*
+    };
+}
+
+macro_rules! avg {
+    ($db:ty: $($t:ty),* => $out:ty) => {
+        $(impl Avg<$db> for $t {
+            type Output = $out;
+        })*
+    };
+}
+
+#[cfg(feature = "sqlite")]
+sum!(sqlx::Sqlite: i8, i16, i32, i64, u8, u16, u32 => i64);
+#[cfg(feature = "sqlite")]
+sum!(sqlx::Sqlite: f32, f64 => f64);
+#[cfg(feature = "sqlite")]
+avg!(sqlx::Sqlite: i8, i16, i32, i64, u8, u16, u32, f32, f64 => f64);
+
+#[cfg(all(feature = "mysql", feature = "rust_decimal"))]
+sum!(sqlx::MySql: i8, i16, i32, i64, u8, u16, u32, u64 => sqlx::types::Decimal);
+#[cfg(feature = "mysql")]
+sum!(sqlx::MySql: f32, f64 => f64);
+#[cfg(all(feature = "mysql", feature = "rust_decimal"))]
+avg!(sqlx::MySql: i8, i16, i32, i64, u8, u16, u32, u64 => sqlx::types::Decimal);
+#[cfg(feature = "mysql")]
+avg!(sqlx::MySql: f32, f64 => f64);
+
+#[cfg(feature = "postgres")]
+sum!(sqlx::Postgres: i16, i32 => i64);
+#[cfg(all(feature = "postgres", feature = "rust_decimal"))]
+sum!(sqlx::Postgres: i64 => sqlx::types::Decimal);
+#[cfg(feature = "postgres")]
+sum!(sqlx::Postgres: f32 => f32);
+#[cfg(feature = "postgres")]
+sum!(sqlx::Postgres: f64 => f64);
+#[cfg(all(feature = "postgres", feature = "rust_decimal"))]
+avg!(sqlx::Postgres: i16, i32, i64 => sqlx::types::Decimal);
+#[cfg(feature = "postgres")]
+avg!(sqlx::Postgres: f32, f64 => f64);
+
+/// `SELECT <expr> FROM <table>` of `M`, read as one value of type `O`.
+pub struct Aggregate<M: Model, O> {
+    expr: String,
+    filters: Vec<String>,
+    binds: Vec<Binder<M::Database>>,
+    _types: PhantomData<fn() -> (M, O)>,
+}
+
+/// `COUNT(*)`
+pub fn count<M: Model>() -> Aggregate<M, i64> {
+    Aggregate::new("COUNT(*)".to_string())
+}
+
+/// `SUM(column)`, `None` without rows.
+pub fn sum<M, T, K>(column: Column<M, T, K>) -> Aggregate<M, Option<T::Output>>
+where
+    M: Model,
+    T: Sum<M::Database>,
+{
+    Aggregate::new(format!("SUM({})", column.name()))
+}
+
+/// `AVG(column)`, `None` without rows.
+pub fn avg<M, T, K>(column: Column<M, T, K>) -> Aggregate<M, Option<T::Output>>
+where
+    M: Model,
+    T: Avg<M::Database>,
+{
+    Aggregate::new(format!("AVG({})", column.name()))
+}
+
+/// `MIN(column)`, `None` without rows.
+pub fn min<M: Model, T, K>(column: Column<M, T, K>) -> Aggregate<M, Option<T>> {
+    Aggregate::new(format!("MIN({})", column.name()))
+}
+
+/// `MAX(column)`, `None` without rows.
+pub fn max<M: Model, T, K>(column: Column<M, T, K>) -> Aggregate<M, Option<T>> {
+    Aggregate::new(format!("MAX({})", column.name()))
+}
+
+impl<M: Model, O> Aggregate<M, O> {
+    fn new(expr: String) -> Self {
+        Aggregate {
+            expr,
+            filters: Vec::new(),
+            binds: Vec::new(),
+            _types: PhantomData,
+        }
+    }
+
+    /// Adds a `WHERE` condition; several filters are joined with `AND`.
+    pub fn filter(mut self, condition: &str) -> Self {
+        self.filters.push(condition.to_string());
+        self
+    }
+
+    /// Binds the next placeholder of the `WHERE` conditions.
+    pub fn bind<T>(mut self, value: T) -> Self
+    where
+        T: for<'q> sqlx::Encode<'q, M::Database> + Type<M::Database> + Send + 'static,
+    {
+        self.binds.push(Box::new(move |q| q.bind(value)));
+        self
+    }
+
+    pub fn sql(&self) -> String {
+        let mut sql = format!("SELECT {} FROM {}", self.expr, M::TABLE);
+        push_filters(&mut sql, &self.filters);
+        sql
+    }
+
+    pub async fn fetch_one<'c, E>(self, executor: E) -> Result<O, sqlx::Error>
+    where
+        E: Executor<'c, Database = M::Database>,
+        for<'q> <M::Database as Database>::Arguments<'q>: IntoArguments<'q, M::Database>,
+        O: for<'r> Decode<'r, M::Database> + Type<M::Database>,
+        usize: ColumnIndex<<M::Database as Database>::Row>,
+    {
+        let sql = self.sql();
+        let mut query = sqlx::query::<M::Database>(&sql);
+        for bind in self.binds {
+            query = bind(query);
+        }
+        query.fetch_one(executor).await?.try_get(0)
+    }
+}
+
+/// Rows of `M` grouped by a column with key type `K`; see [`GroupBy::count`].
+pub struct GroupBy<M: Model, K> {
+    column: &'static str,
+    filters: Vec<String>,
+    binds: Vec<Binder<M::Database>>,
+    _types: PhantomData<fn() -> (M, K)>,
+}
+
+/// `GROUP BY column`
+pub fn group_by<M: Model, T, K>(column: Column<M, T, K>) -> GroupBy<M, K> {
+    GroupBy {
+        column: column.name(),
+        filters: Vec::new(),
+        binds: Vec::new(),
+        _types: PhantomData,
+    }
+}
+
+impl<M: Model, K> GroupBy<M, K> {
+    /// Adds a `WHERE` condition, applied before grouping.
+    pub fn filter(mut self, condition: &str) -> Self {
+        self.filters.push(condition.to_string());
+        self
+    }
+
+    /// Binds the next placeholder of the `WHERE` conditions.
+    pub fn bind<T>(mut self, value: T) -> Self
+    where
+        T: for<'q> sqlx::Encode<'q, M::Database> + Type<M::Database> + Send + 'static,
+    {
+        self.binds.push(Box::new(move |q| q.bind(value)));
+        self
+    }
+
+    /// `(key, COUNT(*))` per group, ordered by key.
+    pub fn count(self) -> Grouped<M, K, i64> {
+        Grouped {
+            group: self,
+            expr: "COUNT(*)".to_string(),
+            _value: PhantomData,
+        }
+    }
+}
+
+/// `(key, value)` rows of a [`GroupBy`].
+pub struct Grouped<M: Model, K, V> {
+    group: GroupBy<M, K>,
+    expr: String,
+    _value: PhantomData<fn() -> V>,
+}
+
+impl<M: Model, K, V> Grouped<M, K, V> {
+    pub fn sql(&self) -> String {
+        let column = self.group.column;
+        let mut sql = format!("SELECT {}, {} FROM {}", column, self.expr, M::TABLE);
+        push_filters(&mut sql, &self.group.filters);
+        sql.push_str(&format!(" GROUP BY {} ORDER BY {}", column, column));
+        sql
+    }
+
+    pub async fn fetch_all<'c, E>(self, executor: E) -> Result<Vec<(K, V)>, sqlx::Error>
+    where
+        E: Executor<'c, Database = M::Database>,
+        for<'q> <M::Database as Database>::Arguments<'q>: IntoArguments<'q, M::Database>,
+        K: for<'r> Decode<'r, M::Database> + Type<M::Database>,
+        V: for<'r> Decode<'r, M::Database> + Type<M::Database>,
+        usize: ColumnIndex<<M::Database as Database>::Row>,
+    {
+        let sql = self.sql();
+        let mut query = sqlx::query::<M::Database>(&sql);
+        for bind in self.group.binds {
+            query = bind(query);
+        }
+        let rows = query.fetch_all(executor).await?;
+        rows.iter().map(|row| Ok((row.try_get(0)?, row.try_get(1)?))).collect()
+    }
+}
+
+fn push_filters(sql: &mut String, filters: &[String]) {
+    if !filters.is_empty() {
+        sql.push_str(&format!(" WHERE {}", filters.join(" AND ")));
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sql::PlaceholderStyle;
+
+/// Database a derived struct was generated for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Sqlite,
+    MySql,
+    Postgres,
+}
+
+impl Backend {
+    /// Maps `sqlx` backend names (`AnyConnection::backend_name`, `DatabaseName`) to a
+    /// backend.
+    pub fn from_name(name: &str) -> Option<Backend> {
+        match name.to_lowercase().as_str() {
+            "sqlite" => Some(Backend::Sqlite),
+            "mysql" | "mariadb" => Some(Backend::MySql),
+            "postgres" | "postgresql" => Some(Backend::Postgres),
+            _ => None,
+        }
+    }
+
+    /// Backend behind an `AnyConnection`, which decides the SQL dialect of structs
+    /// derived with `driver = "any"`.
+    pub fn of_any(conn: &sqlx::AnyConnection) -> Result<Backend, sqlx::Error> {
+        let name = conn.backend_name();
+        Backend::from_name(name)
+            .ok_or_else(|| sqlx::Error::Configuration(format!("unsupported backend `{}`", name).into()))
+    }
+
+    pub fn placeholder_style(self) -> PlaceholderStyle {
+        match self {
+            Backend::Postgres => PlaceholderStyle::Dollar,
+            _ => PlaceholderStyle::Question,
+        }
+    }
+}
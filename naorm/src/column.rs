@@ -0,0 +1,45 @@
+//! Typed column handles, generated for every `#[derive(NaormReg)]` struct as the fields
+//! of `{Struct}Columns` and returned by `Struct::columns()`.
+//!
+//! ```ignore
+//! let total: Option<i64> = BookNote::sum_query(BookNote::columns().pages).fetch_one(&pool).await?;
+//! ```
+use std::fmt;
+use std::marker::PhantomData;
+
+type Types<M, T, K> = PhantomData<fn() -> (M, T, K)>;
+
+/// Column `name` of the model `M`. `T` is the type of its values (without `Option`), `K`
+/// the type a row holds: `T`, or `Option<T>` for nullable columns.
+pub struct Column<M, T, K = T> {
+    name: &'static str,
+    _types: Types<M, T, K>,
+}
+
+impl<M, T, K> Column<M, T, K> {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str) -> Self {
+        Column {
+            name,
+            _types: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<M, T, K> Clone for Column<M, T, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M, T, K> Copy for Column<M, T, K> {}
+
+impl<M, T, K> fmt::Debug for Column<M, T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Column").field(&self.name).finish()
+    }
+}
@@ -0,0 +1,54 @@
+//! Backends of structs derived for several drivers (`drivers = "sqlite, postgres"`),
+//! whose methods are generic over the database of the executor.
+use std::future::Future;
+
+use crate::Backend;
+use crate::schema::{self, LiveColumn};
+
+/// A `sqlx` database the derive generates SQL for.
+pub trait NaormDialect: sqlx::Database {
+    const BACKEND: Backend;
+
+    /// Columns of `table` as reported by the database.
+    fn live_columns<'e, E>(
+        executor: E,
+        table: &'e str,
+    ) -> impl Future<Output = Result<Vec<LiveColumn>, sqlx::Error>> + Send + 'e
+    where
+        E: sqlx::Executor<'e, Database = Self> + 'e;
+}
+
+/// Implemented by the derive for every driver listed in `drivers = "..."`.
+pub trait Supports<DB: sqlx::Database> {}
+
+macro_rules! dialect {
+    ($db:ty, $backend:expr) => {
+        impl NaormDialect for $db {
+            const BACKEND: Backend = $backend;
+
+            fn live_columns<'e, E>(
+                executor: E,
+                table: &'e str,
+            ) -> impl Future<Output = Result<Vec<LiveColumn>, sqlx::Error>> + Send + 'e
+            where
+                E: sqlx::Executor<'e, Database = Self> + 'e,
+            {
+                async move {
+                    let rows: Vec<(String, String, String, Option<String>)> =
+                        sqlx::query_as(schema::introspect_sql(Self::BACKEND))
+                            .bind(table)
+                            .fetch_all(executor)
+                            .await?;
+                    Ok(rows.into_iter().map(LiveColumn::from).collect())
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "sqlite")]
+dialect!(sqlx::Sqlite, Backend::Sqlite);
+#[cfg(feature = "mysql")]
+dialect!(sqlx::MySql, Backend::MySql);
+#[cfg(feature = "postgres")]
+dialect!(sqlx::Postgres, Backend::Postgres);
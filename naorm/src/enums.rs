@@ -0,0 +1,72 @@
+//! Column descriptions of fieldless enums, implemented by `#[derive(NaormEnum)]`.
+//!
+//! ```ignore
+//! #[derive(naorm::NaormEnum, Debug, Clone, Copy, PartialEq)]
+//! #[naorm_enum(repr = "text", native, type_name = "book_color")]
+//! enum Color {
+//!     Red,
+//!     #[naorm_enum(rename = "sea-green")]
+//!     SeaGreen,
+//! }
+//! ```
+//!
+//! Mark the struct field with `#[naorm_cfg(enum)]` so the generated DDL picks the
+//! column type and `CHECK (col IN (...))` constraint from these constants.
+
+/// Stored representation and DDL pieces of an enum column.
+pub trait NaormEnum: Sized + 'static {
+    /// Name of the native postgres type.
+    const TYPE_NAME: &'static str;
+    /// `'a', 'b'` for text enums, `0, 1` for integer enums.
+    const CHECK_VALUES: &'static str;
+    const SQLITE_TYPE: &'static str;
+    const MYSQL_TYPE: &'static str;
+    const POSTGRES_TYPE: &'static str;
+    /// Statement creating the native postgres type, empty unless `native`.
+    const POSTGRES_CREATE_TYPE_SQL: &'static str;
+
+    /// The stored text, or the decimal discriminant for integer enums.
+    fn as_sql_str(&self) -> &'static str;
+}
+
+#[doc(hidden)]
+#[cfg(feature = "sqlite")]
+#[macro_export]
+macro_rules! __naorm_sqlite {
+    ($($t:tt)*) => { $($t)* };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "sqlite"))]
+#[macro_export]
+macro_rules! __naorm_sqlite {
+    ($($t:tt)*) => {};
+}
+
+#[doc(hidden)]
+#[cfg(feature = "mysql")]
+#[macro_export]
+macro_rules! __naorm_mysql {
+    ($($t:tt)*) => { $($t)* };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "mysql"))]
+#[macro_export]
+macro_rules! __naorm_mysql {
+    ($($t:tt)*) => {};
+}
+
+#[doc(hidden)]
+#[cfg(feature = "postgres")]
+#[macro_export]
+macro_rules! __naorm_postgres {
+    ($($t:tt)*) => { $($t)* };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "postgres"))]
+#[macro_export]
+macro_rules! __naorm_postgres {
+    ($($t:tt)*) => {};
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+
+use crate::validate::ValidationErrors;
+
+/// Error returned by the generated `insert`/`update` helpers and [`crate::UnitOfWork`].
+#[derive(Debug)]
+pub enum Error {
+    /// The value was rejected by `validate()` before reaching the database.
+    Validation(ValidationErrors),
+    Database(sqlx::Error),
+    /// A value of the table, whose key the database generates (`auto_increment`),
+    /// was queued for insertion in a [`crate::UnitOfWork`], which reads no key back.
+    GeneratedKey(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Validation(e) => write!(f, "validation failed: {}", e),
+            Error::Database(e) => write!(f, "{}", e),
+            Error::GeneratedKey(table) => {
+                write!(f, "{}: rows with a generated key can not be inserted in a unit of work", table)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Validation(e) => Some(e),
+            Error::Database(e) => Some(e),
+            Error::GeneratedKey(_) => None,
+        }
+    }
+}
+
+impl From<ValidationErrors> for Error {
+    fn from(e: ValidationErrors) -> Self {
+        Error::Validation(e)
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Self {
+        Error::Database(e)
+    }
+}
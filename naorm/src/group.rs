@@ -0,0 +1,336 @@
+//! Column groups, implemented by `#[derive(NaormColumns)]`: structs whose fields are
+//! stored as columns of every table embedding them.
+//!
+//! ```ignore
+//! #[derive(naorm::NaormColumns, Debug, Default)]
+//! struct Address {
+//!     street: String,
+//!     city: String,
+//!     zip: Option<String>,
+//! }
+//!
+//! #[derive(naorm::NaormReg)]
+//! struct Customer {
+//!     #[naorm_cfg(primary_key)]
+//!     id: i64,
+//!     #[naorm_cfg(flatten, prefix = "billing_")]
+//!     billing: Address,
+//! }
+//! ```
+//!
+//! The columns of the group take the place of the field in the SQL constants, the
+//! binds and the DDL of the struct, named `{prefix}{column}`.
+use crate::{Backend, FieldInfo};
+use crate::schema::ColumnSchema;
+use crate::sql::DialectSql;
+
+/// Marks where the prefix of the embedding field goes in [`GroupColumn::definition`].
+pub const PREFIX_MARKER: u8 = 0x02;
+
+/// A column of a [`ColumnGroup`], named without prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GroupColumn {
+    pub name: &'static str,
+    /// Field type as listed in `NAORM_FIELDS`.
+    pub field_type: &'static str,
+    pub nullable: bool,
+    /// Default as listed in `NAORM_FIELDS`.
+    pub default_value: &'static str,
+    /// Default as it appears in the DDL (already quoted).
+    pub default_sql: Option<&'static str>,
+    pub sql_type: DialectSql,
+    /// Column definition, with [`PREFIX_MARKER`] before every use of the name.
+    pub definition: DialectSql,
+}
+
+/// Columns of a struct embedded with `#[naorm_cfg(flatten)]`.
+pub trait ColumnGroup: Sized {
+    const COLUMNS: &'static [GroupColumn];
+
+    /// Checks the `naorm_cfg` validation rules of the fields, called by the
+    /// `validate()` of the embedding struct.
+    fn validate_columns(&self, errors: &mut crate::ValidationErrors) {
+        let _ = errors;
+    }
+}
+
+/// Binds and decodes the columns of a [`ColumnGroup`] with one database; implemented
+/// for the drivers enabled as naorm features.
+pub trait GroupCodec<DB: sqlx::Database>: ColumnGroup {
+    /// `query` with the columns bound in [`ColumnGroup::COLUMNS`] order.
+    fn bind_columns<'q>(
+        &'q self,
+        query: sqlx::query::Query<'q, DB, <DB as sqlx::Database>::Arguments<'q>>,
+    ) -> sqlx::query::Query<'q, DB, <DB as sqlx::Database>::Arguments<'q>>;
+
+    /// Decodes the group from columns named `{prefix}{column}`.
+    fn decode_columns(row: &<DB as sqlx::Database>::Row, prefix: &str) -> Result<Self, sqlx::Error>;
+}
+
+/// Schema of the columns of `G` embedded with `prefix`, for `Model::schema()`.
+pub fn schema_columns<G: ColumnGroup>(backend: Backend, prefix: &str) -> Vec<ColumnSchema> {
+    G::COLUMNS
+        .iter()
+        .map(|c| ColumnSchema {
+            name: format!("{}{}", prefix, c.name),
+            sql_type: c.sql_type.get(backend).to_string(),
+            nullable: c.nullable,
+            primary_key: false,
+            auto_increment: false,
+            default: c.default_sql.map(str::to_string),
+            definition: c.definition.get(backend).replace(PREFIX_MARKER as char, prefix),
+            renamed_from: None,
+        })
+        .collect()
+}
+
+/// Part of a SQL constant of a struct embedding groups, rendered at compile time by
+/// [`render`]. Placeholders are numbered in order for postgres.
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub enum Piece {
+    Str(&'static str),
+    /// One placeholder.
+    Param,
+    /// `{prefix}a, {prefix}b`
+    Names(&'static str, &'static [GroupColumn]),
+    /// One placeholder per column, comma separated.
+    Params(&'static [GroupColumn]),
+    /// `{prefix}a = ?, {prefix}b = ?`
+    Assign(&'static str, &'static [GroupColumn]),
+    /// The column definitions in the dialect of the backend.
+    Definitions(&'static str, &'static [GroupColumn], Backend),
+}
+
+struct Out<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    numbered: bool,
+    params: usize,
+}
+
+impl Out<'_> {
+    // measures only while `buf` is empty
+    const fn byte(&mut self, b: u8) {
+        if self.len < self.buf.len() {
+            self.buf[self.len] = b;
+        }
+        self.len += 1;
+    }
+
+    const fn str(&mut self, s: &str) {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            self.byte(bytes[i]);
+            i += 1;
+        }
+    }
+
+    const fn prefixed(&mut self, s: &str, prefix: &str) {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == PREFIX_MARKER {
+                self.str(prefix);
+            } else {
+                self.byte(bytes[i]);
+            }
+            i += 1;
+        }
+    }
+
+    const fn param(&mut self) {
+        self.params += 1;
+        if !self.numbered {
+            self.byte(b'?');
+            return;
+        }
+        self.byte(b'$');
+        let mut div = 1;
+        while div * 10 <= self.params {
+            div *= 10;
+        }
+        while div > 0 {
+            self.byte(b'0' + (self.params / div % 10) as u8);
+            div /= 10;
+        }
+    }
+}
+
+const fn write(pieces: &[Piece], numbered: bool, buf: &mut [u8]) -> usize {
+    let mut out = Out {
+        buf,
+        len: 0,
+        numbered,
+        params: 0,
+    };
+    let mut i = 0;
+    while i < pieces.len() {
+        match pieces[i] {
+            Piece::Str(s) => out.str(s),
+            Piece::Param => out.param(),
+            Piece::Names(prefix, columns) | Piece::Assign(prefix, columns) => {
+                let assign = matches!(pieces[i], Piece::Assign(..));
+                let mut j = 0;
+                while j < columns.len() {
+                    if j > 0 {
+                        out.str(", ");
+                    }
+                    out.str(prefix);
+                    out.str(columns[j].name);
+                    if assign {
+                        out.str(" = ");
+                        out.param();
+                    }
+                    j += 1;
+                }
+            }
+            Piece::Params(columns) => {
+                let mut j = 0;
+                while j < columns.len() {
+                    if j > 0 {
+                        out.str(", ");
+                    }
+                    out.param();
+                    j += 1;
+                }
+            }
+            Piece::Definitions(prefix, columns, backend) => {
+                let mut j = 0;
+                while j < columns.len() {
+                    if j > 0 {
+                        out.str(",\n    ");
+                    }
+                    out.prefixed(columns[j].definition.get(backend), prefix);
+                    j += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+    out.len
+}
+
+/// Length of the rendered `pieces`, the array size for [`render`].
+#[doc(hidden)]
+pub const fn render_len(pieces: &[Piece], numbered: bool) -> usize {
+    write(pieces, numbered, &mut [])
+}
+
+#[doc(hidden)]
+pub const fn render<const N: usize>(pieces: &[Piece], numbered: bool) -> [u8; N] {
+    let mut out = [0u8; N];
+    write(pieces, numbered, &mut out);
+    out
+}
+
+/// Length of the names of `columns` with `prefix`, the array size for [`prefixed_names`].
+#[doc(hidden)]
+pub const fn prefixed_names_len(prefix: &str, columns: &[GroupColumn]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < columns.len() {
+        len += prefix.len() + columns[i].name.len();
+        i += 1;
+    }
+    len
+}
+
+/// The names of `columns` with `prefix`, concatenated; sliced apart by [`split_names`].
+#[doc(hidden)]
+pub const fn prefixed_names<const N: usize>(prefix: &str, columns: &[GroupColumn]) -> [u8; N] {
+    let mut out = [0u8; N];
+    let mut o = Out {
+        buf: &mut out,
+        len: 0,
+        numbered: false,
+        params: 0,
+    };
+    let mut i = 0;
+    while i < columns.len() {
+        o.str(prefix);
+        o.str(columns[i].name);
+        i += 1;
+    }
+    out
+}
+
+/// The prefixed column names in `bytes` (see [`prefixed_names`]) as separate strings.
+#[doc(hidden)]
+pub const fn split_names<const M: usize>(
+    bytes: &'static [u8],
+    prefix: &str,
+    columns: &[GroupColumn],
+) -> [&'static str; M] {
+    let mut out = [""; M];
+    let mut rest = bytes;
+    let mut i = 0;
+    while i < M {
+        let (name, tail) = rest.split_at(prefix.len() + columns[i].name.len());
+        out[i] = crate::__private::utf8(name);
+        rest = tail;
+        i += 1;
+    }
+    out
+}
+
+/// `NAORM_FIELDS` entries of the prefixed `names` of `columns`, with the column type of
+/// `backend` (empty without one).
+#[doc(hidden)]
+pub const fn fields<const M: usize>(
+    names: &[&'static str],
+    columns: &[GroupColumn],
+    backend: Option<Backend>,
+) -> [FieldInfo; M] {
+    let mut out = [FieldInfo::EMPTY; M];
+    let mut i = 0;
+    while i < M {
+        let c = columns[i];
+        let sql_type = match backend {
+            Some(backend) => c.sql_type.get(backend),
+            None => "",
+        };
+        out[i] = FieldInfo {
+            name: names[i],
+            field_type: c.field_type,
+            is_option: c.nullable,
+            is_auto_increment: false,
+            is_primary_key: false,
+            default_value: c.default_value,
+            sql_type,
+        };
+        i += 1;
+    }
+    out
+}
+
+/// Total length of `parts`, the array size for [`concat_slices`].
+#[doc(hidden)]
+pub const fn slices_len<T>(parts: &[&[T]]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        len += parts[i].len();
+        i += 1;
+    }
+    len
+}
+
+/// `parts` joined at compile time; `fill` is overwritten.
+#[doc(hidden)]
+pub const fn concat_slices<T: Copy, const N: usize>(parts: &[&[T]], fill: T) -> [T; N] {
+    let mut out = [fill; N];
+    let mut at = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        let mut j = 0;
+        while j < parts[i].len() {
+            out[at] = parts[i][j];
+            at += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    out
+}
@@ -0,0 +1,218 @@
+//! Joins over two derived types.
+//!
+//! Both column lists are selected with `table__column` aliases, so columns present in
+//! both tables (`id`, ...) do not collide, and each side is decoded with
+//! [`Model::from_row_prefixed`]. The tables are not aliased, so a type can not be
+//! joined with itself: `inner::<Node, Node>` fails to build. The right side of a
+//! [`left`] join needs a primary key: a NULL key marks a row without a match, while
+//! other columns may be NULL in a matched row.
+//!
+//! ```ignore
+//! let rows: Vec<(BookNote, Book)> =
+//!     naorm::join::inner::<BookNote, Book>("book_note.book_id = book.id")
+//!         .filter("book.title = ?")
+//!         .bind("Dune".to_string())
+//!         .fetch_all(&pool)
+//!         .await?;
+//! ```
+use std::marker::PhantomData;
+
+use sqlx::query::Query;
+use sqlx::{ColumnIndex, Database, Executor, IntoArguments, Row, ValueRef};
+
+use crate::Model;
+
+type DbRow<M> = <<M as Model>::Database as Database>::Row;
+type BoundQuery<'q, DB> = Query<'q, DB, <DB as Database>::Arguments<'q>>;
+type Binder<DB> = Box<dyn for<'q> FnOnce(BoundQuery<'q, DB>) -> BoundQuery<'q, DB> + Send>;
+
+/// Prefix of the aliased columns of `M` in a join.
+pub fn prefix<M: Model>() -> String {
+    format!("{}__", M::TABLE)
+}
+
+/// `INNER JOIN` or `LEFT JOIN`, deciding how the right side is decoded.
+pub trait JoinKind<A: Model, B: Model> {
+    const KEYWORD: &'static str;
+    type Output;
+
+    fn decode(row: &DbRow<A>) -> Result<Self::Output, sqlx::Error>;
+}
+
+pub struct Inner;
+pub struct Left;
+
+impl<A, B> JoinKind<A, B> for Inner
+where
+    A: Model,
+    B: Model<Database = A::Database>,
+{
+    const KEYWORD: &'static str = "INNER JOIN";
+    type Output = (A, B);
+
+    fn decode(row: &DbRow<A>) -> Result<(A, B), sqlx::Error> {
+        Ok((
+            A::from_row_prefixed(row, &prefix::<A>())?,
+            B::from_row_prefixed(row, &prefix::<B>())?,
+        ))
+    }
+}
+
+impl<A, B> JoinKind<A, B> for Left
+where
+    A: Model,
+    B: Model<Database = A::Database>,
+    for<'s> &'s str: ColumnIndex<DbRow<A>>,
+{
+    const KEYWORD: &'static str = "LEFT JOIN";
+    type Output = (A, Option<B>);
+
+    fn decode(row: &DbRow<A>) -> Result<(A, Option<B>), sqlx::Error> {
+        let a = A::from_row_prefixed(row, &prefix::<A>())?;
+        // a missing right side shows up as NULL in its primary key, checked by `left`
+        let probe = format!("{}{}", prefix::<B>(), B::PK);
+        if row.try_get_raw(probe.as_str())?.is_null() {
+            return Ok((a, None));
+        }
+        Ok((a, Some(B::from_row_prefixed(row, &prefix::<B>())?)))
+    }
+}
+
+/// A `SELECT` over `A JOIN B`; see the module docs.
+pub struct Join<A: Model, B: Model, K> {
+    on: String,
+    filters: Vec<String>,
+    order_by: Option<String>,
+    limit: Option<u64>,
+    binds: Vec<Binder<A::Database>>,
+    _models: PhantomData<fn() -> (A, B)>,
+    _kind: PhantomData<K>,
+}
+
+/// `A INNER JOIN B ON <on>`, decoding rows into `(A, B)`.
+pub fn inner<A, B>(on: &str) -> Join<A, B, Inner>
+where
+    A: Model,
+    B: Model<Database = A::Database>,
+{
+    Join::new(on)
+}
+
+/// `A LEFT JOIN B ON <on>`, decoding rows into `(A, Option<B>)`. `B` needs a primary
+/// key, whose NULL tells a missing right side.
+pub fn left<A, B>(on: &str) -> Join<A, B, Left>
+where
+    A: Model,
+    B: Model<Database = A::Database>,
+    for<'s> &'s str: ColumnIndex<DbRow<A>>,
+{
+    const { assert!(!B::PK.is_empty(), "naorm::join::left: the right-hand type has no primary key") };
+    Join::new(on)
+}
+
+impl<A, B, K> Join<A, B, K>
+where
+    A: Model,
+    B: Model<Database = A::Database>,
+    K: JoinKind<A, B>,
+{
+    fn new(on: &str) -> Self {
+        // `FROM node INNER JOIN node` would be ambiguous, and both sides would share
+        // their column aliases
+        const {
+            assert!(
+                !crate::__private::str_eq(A::TABLE, B::TABLE),
+                "naorm::join: both sides use the same table, self-joins are not supported"
+            )
+        };
+        Join {
+            on: on.to_string(),
+            filters: Vec::new(),
+            order_by: None,
+            limit: None,
+            binds: Vec::new(),
+            _models: PhantomData,
+            _kind: PhantomData,
+        }
+    }
+
+    /// Adds a `WHERE` condition; several filters are joined with `AND`.
+    pub fn filter(mut self, condition: &str) -> Self {
+        self.filters.push(condition.to_string());
+        self
+    }
+
+    pub fn order_by(mut self, order: &str) -> Self {
+        self.order_by = Some(order.to_string());
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Binds the next placeholder of the `ON`/`WHERE` conditions.
+    pub fn bind<T>(mut self, value: T) -> Self
+    where
+        T: for<'q> sqlx::Encode<'q, A::Database> + sqlx::Type<A::Database> + Send + 'static,
+    {
+        self.binds.push(Box::new(move |q| q.bind(value)));
+        self
+    }
+
+    pub fn sql(&self) -> String {
+        let columns = |table: &str, cols: &[&str]| {
+            cols.iter()
+                .map(|c| format!("{t}.{c} AS {t}__{c}", t = table, c = c))
+                .collect::<Vec<_>>()
+        };
+        let mut select = columns(A::TABLE, A::COLUMNS);
+        select.extend(columns(B::TABLE, B::COLUMNS));
+        let mut sql = format!(
+            "SELECT {} FROM {} {} {} ON {}",
+            select.join(", "),
+            A::TABLE,
+            K::KEYWORD,
+            B::TABLE,
+            self.on
+        );
+        if !self.filters.is_empty() {
+            sql.push_str(&format!(" WHERE {}", self.filters.join(" AND ")));
+        }
+        if let Some(order) = &self.order_by {
+            sql.push_str(&format!(" ORDER BY {}", order));
+        }
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        sql
+    }
+
+    pub async fn fetch_all<'c, E>(self, executor: E) -> Result<Vec<K::Output>, sqlx::Error>
+    where
+        E: Executor<'c, Database = A::Database>,
+        for<'q> <A::Database as Database>::Arguments<'q>: IntoArguments<'q, A::Database>,
+    {
+        let sql = self.sql();
+        let mut query = sqlx::query::<A::Database>(&sql);
+        for bind in self.binds {
+            query = bind(query);
+        }
+        let rows = query.fetch_all(executor).await?;
+        rows.iter().map(K::decode).collect()
+    }
+
+    pub async fn fetch_optional<'c, E>(self, executor: E) -> Result<Option<K::Output>, sqlx::Error>
+    where
+        E: Executor<'c, Database = A::Database>,
+        for<'q> <A::Database as Database>::Arguments<'q>: IntoArguments<'q, A::Database>,
+    {
+        let sql = self.sql();
+        let mut query = sqlx::query::<A::Database>(&sql);
+        for bind in self.binds {
+            query = bind(query);
+        }
+        query.fetch_optional(executor).await?.as_ref().map(K::decode).transpose()
+    }
+}
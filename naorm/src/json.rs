@@ -0,0 +1,26 @@
+//! Path conversions used by the generated `{field}_path_query` of `json` fields.
+//!
+//! Paths are written dotted, `theme.color` or `tags[0].name`, for every driver.
+
+/// `$.theme.color`, the path syntax of sqlite `json_extract` and mysql `JSON_EXTRACT`.
+/// Paths already starting with `$` are kept.
+pub fn json_path(path: &str) -> String {
+    if path.starts_with('$') {
+        return path.to_string();
+    }
+    if path.starts_with('[') {
+        format!("${}", path)
+    } else {
+        format!("$.{}", path)
+    }
+}
+
+/// `{theme,color}` elements for the postgres `#>>` operator.
+pub fn pg_path(path: &str) -> Vec<String> {
+    path.trim_start_matches('$')
+        .split(['.', '['])
+        .map(|s| s.trim_end_matches(']'))
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,95 @@
+//! Runtime support for the `naorm_macro` derives.
+//!
+//! The code generated by `#[derive(NaormReg)]` refers to the items of this crate, so
+//! add it next to `sqlx` in your dependencies and import the derive from here.
+extern crate self as naorm;
+
+pub mod aggregate;
+pub mod backend;
+pub mod column;
+pub mod dialect;
+pub mod enums;
+pub mod error;
+pub mod group;
+pub mod join;
+pub mod json;
+pub mod migrate;
+pub mod model;
+pub mod projection;
+pub mod schema;
+pub mod search;
+pub mod sql;
+pub mod stream;
+pub mod unit_of_work;
+pub mod validate;
+
+pub use backend::Backend;
+pub use column::Column;
+pub use dialect::NaormDialect;
+pub use enums::NaormEnum;
+pub use error::Error;
+pub use group::ColumnGroup;
+pub use model::{FieldInfo, Model};
+pub use naorm_macro::{NaormColumns, NaormEnum, NaormProjection, NaormReg};
+pub use unit_of_work::{Persist, UnitOfWork};
+pub use validate::{ValidationError, ValidationErrors};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use regex;
+    pub use serde;
+    pub use serde_json;
+
+    /// `str` equality usable in the const assertions emitted by the derive.
+    pub const fn str_eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Total length of `parts`, the array size for [`concat`].
+    pub const fn concat_len(parts: &[&str]) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+        while i < parts.len() {
+            len += parts[i].len();
+            i += 1;
+        }
+        len
+    }
+
+    /// `parts` joined at compile time; DDL referring to constants of other types
+    /// (enum columns) is assembled with it.
+    pub const fn concat<const N: usize>(parts: &[&str]) -> [u8; N] {
+        let mut out = [0u8; N];
+        let mut at = 0;
+        let mut i = 0;
+        while i < parts.len() {
+            let bytes = parts[i].as_bytes();
+            let mut j = 0;
+            while j < bytes.len() {
+                out[at] = bytes[j];
+                at += 1;
+                j += 1;
+            }
+            i += 1;
+        }
+        out
+    }
+
+    pub const fn utf8(bytes: &[u8]) -> &str {
+        match core::str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(_) => panic!("naorm: concatenated SQL is not valid UTF-8"),
+        }
+    }
+}
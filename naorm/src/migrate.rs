@@ -0,0 +1,288 @@
+//! Migration generation from struct diffs.
+//!
+//! [`Migrator`] compares the schemas of the derived structs (`Model::schema()`) with
+//! the snapshot written by its previous run and emits a reversible migration in the
+//! `sqlx::migrate!` layout (`<version>_<name>.up.sql` / `.down.sql`). Tables found in
+//! the snapshot but not passed to [`Migrator::generate`] are dropped, so always pass
+//! every derived struct.
+//!
+//! ```ignore
+//! naorm::migrate::Migrator::new("migrations")
+//!     .generate("add_note_color", &[Book::schema(), BookNote::schema()])?;
+//! ```
+//!
+//! Column renames can not be told apart from drop + add, mark them on the field with
+//! `#[naorm_cfg(renamed_from = "old_name")]`. SQLite has no `ALTER COLUMN`; type,
+//! nullability and default changes rebuild the table instead.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Backend;
+use crate::schema::{ColumnSchema, IndexSchema, TableSchema};
+
+/// Files written by [`Migrator::generate`].
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: String,
+    pub up: String,
+    pub down: String,
+    pub up_path: PathBuf,
+    pub down_path: PathBuf,
+}
+
+pub struct Migrator {
+    dir: PathBuf,
+    snapshot: PathBuf,
+    version: Option<String>,
+}
+
+impl Migrator {
+    /// Writes migrations to `dir` and keeps the snapshot in `dir/naorm_schema.json`
+    /// (ignored by `sqlx::migrate!`, which only reads `*.sql`).
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        let dir = dir.as_ref().to_path_buf();
+        Migrator {
+            snapshot: dir.join("naorm_schema.json"),
+            dir,
+            version: None,
+        }
+    }
+
+    pub fn snapshot(mut self, path: impl AsRef<Path>) -> Self {
+        self.snapshot = path.as_ref().to_path_buf();
+        self
+    }
+
+    /// Uses a fixed version instead of the current UTC `YYYYMMDDHHMMSS` timestamp.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    pub fn load_snapshot(&self) -> io::Result<Vec<TableSchema>> {
+        match fs::read_to_string(&self.snapshot) {
+            Ok(s) => Ok(serde_json::from_str(&s)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Diffs `tables` against the snapshot, writes the migration files and the new
+    /// snapshot. Returns `None` when nothing changed.
+    pub fn generate(&self, name: &str, tables: &[TableSchema]) -> io::Result<Option<Migration>> {
+        let old = self.load_snapshot()?;
+        let (up, down) = diff(&old, tables);
+        if up.is_empty() {
+            return Ok(None);
+        }
+        let version = self.version.clone().unwrap_or_else(utc_version);
+        let name = name.trim().replace([' ', '-'], "_");
+        fs::create_dir_all(&self.dir)?;
+        let up_path = self.dir.join(format!("{}_{}.up.sql", version, name));
+        let down_path = self.dir.join(format!("{}_{}.down.sql", version, name));
+        let up = render(&up);
+        let down = render(&down);
+        fs::write(&up_path, &up)?;
+        fs::write(&down_path, &down)?;
+        fs::write(&self.snapshot, serde_json::to_string_pretty(tables)?)?;
+        Ok(Some(Migration {
+            version,
+            up,
+            down,
+            up_path,
+            down_path,
+        }))
+    }
+}
+
+fn render(statements: &[String]) -> String {
+    let mut out = String::from("-- generated by naorm\n");
+    for s in statements {
+        out.push_str(s);
+        out.push_str(";\n");
+    }
+    out
+}
+
+/// Statements migrating `old` to `new` and back, in execution order.
+pub fn diff(old: &[TableSchema], new: &[TableSchema]) -> (Vec<String>, Vec<String>) {
+    let mut up = Vec::new();
+    // one group per table, undone in reverse order
+    let mut down: Vec<Vec<String>> = Vec::new();
+    for table in new {
+        match old.iter().find(|t| t.name == table.name) {
+            None => {
+                up.push(table.create_sql.clone());
+                up.extend(table.indexes.iter().map(|i| create_index(&table.name, i)));
+                down.push(vec![format!("DROP TABLE {}", table.name)]);
+            }
+            Some(prev) => {
+                let renames: Vec<(String, String)> = table
+                    .columns
+                    .iter()
+                    .filter_map(|c| Some((c.renamed_from.clone()?, c.name.clone())))
+                    .collect();
+                let reverse: Vec<(String, String)> =
+                    renames.iter().map(|(a, b)| (b.clone(), a.clone())).collect();
+                up.extend(alter_table(prev, table, &renames));
+                down.push(alter_table(table, prev, &reverse));
+            }
+        }
+    }
+    for table in old.iter().rev() {
+        if !new.iter().any(|t| t.name == table.name) {
+            up.push(format!("DROP TABLE {}", table.name));
+            let mut group = vec![table.create_sql.clone()];
+            group.extend(table.indexes.iter().map(|i| create_index(&table.name, i)));
+            down.push(group);
+        }
+    }
+    (up, down.into_iter().rev().flatten().collect())
+}
+
+fn create_index(table: &str, index: &IndexSchema) -> String {
+    format!(
+        "CREATE {}INDEX {} ON {} ({})",
+        if index.unique { "UNIQUE " } else { "" },
+        index.name,
+        table,
+        index.columns.join(", ")
+    )
+}
+
+fn drop_index(backend: Backend, table: &str, index: &IndexSchema) -> String {
+    match backend {
+        Backend::MySql => format!("DROP INDEX {} ON {}", index.name, table),
+        _ => format!("DROP INDEX {}", index.name),
+    }
+}
+
+fn column_changed(a: &ColumnSchema, b: &ColumnSchema) -> bool {
+    a.sql_type != b.sql_type || a.nullable != b.nullable || a.default != b.default
+}
+
+/// Statements turning table `from` into `to`. `renames` are `(old, new)` column pairs.
+fn alter_table(from: &TableSchema, to: &TableSchema, renames: &[(String, String)]) -> Vec<String> {
+    let t = &to.name;
+    // where each column of `to` comes from
+    let source = |col: &ColumnSchema| -> Option<&ColumnSchema> {
+        let renamed = renames
+            .iter()
+            .find(|(old, new)| new == &col.name && from.column(old).is_some() && from.column(new).is_none());
+        match renamed {
+            Some((old, _)) => from.column(old),
+            None => from.column(&col.name),
+        }
+    };
+    let sourced: Vec<(&ColumnSchema, &ColumnSchema)> = to
+        .columns
+        .iter()
+        .filter_map(|c| source(c).map(|s| (c, s)))
+        .collect();
+    let added: Vec<&ColumnSchema> = to.columns.iter().filter(|c| source(c).is_none()).collect();
+    let dropped: Vec<&ColumnSchema> = from
+        .columns
+        .iter()
+        .filter(|c| !sourced.iter().any(|(_, s)| s.name == c.name))
+        .collect();
+    let changed: Vec<(&ColumnSchema, &ColumnSchema)> = sourced
+        .iter()
+        .copied()
+        .filter(|(c, s)| column_changed(c, s))
+        .collect();
+
+    let mut out = Vec::new();
+    if to.backend == Backend::Sqlite && !changed.is_empty() {
+        let tmp = format!("{}__naorm_new", t);
+        let create = to
+            .create_sql
+            .replacen(&format!("CREATE TABLE IF NOT EXISTS {} (", t), &format!("CREATE TABLE {} (", tmp), 1);
+        out.push(create);
+        let into: Vec<&str> = sourced.iter().map(|(c, _)| c.name.as_str()).collect();
+        let select: Vec<&str> = sourced.iter().map(|(_, s)| s.name.as_str()).collect();
+        out.push(format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {}",
+            tmp,
+            into.join(", "),
+            select.join(", "),
+            t
+        ));
+        out.push(format!("DROP TABLE {}", t));
+        out.push(format!("ALTER TABLE {} RENAME TO {}", tmp, t));
+        out.extend(to.indexes.iter().map(|i| create_index(t, i)));
+        return out;
+    }
+
+    let index_changed = |a: &IndexSchema, other: &TableSchema| !other.indexes.contains(a);
+    for index in from.indexes.iter().filter(|i| index_changed(i, to)) {
+        out.push(drop_index(to.backend, t, index));
+    }
+    for (c, s) in &sourced {
+        if c.name != s.name {
+            out.push(format!("ALTER TABLE {} RENAME COLUMN {} TO {}", t, s.name, c.name));
+        }
+    }
+    for c in &added {
+        out.push(format!("ALTER TABLE {} ADD COLUMN {}", t, c.definition));
+    }
+    for c in &dropped {
+        out.push(format!("ALTER TABLE {} DROP COLUMN {}", t, c.name));
+    }
+    for (c, s) in &changed {
+        match to.backend {
+            Backend::MySql => out.push(format!("ALTER TABLE {} MODIFY COLUMN {}", t, c.definition)),
+            _ => {
+                if c.sql_type != s.sql_type {
+                    out.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{}",
+                        t, c.name, c.sql_type, c.name, c.sql_type
+                    ));
+                }
+                if c.nullable != s.nullable {
+                    let action = if c.nullable { "DROP" } else { "SET" };
+                    out.push(format!("ALTER TABLE {} ALTER COLUMN {} {} NOT NULL", t, c.name, action));
+                }
+                if c.default != s.default {
+                    match &c.default {
+                        Some(d) => out.push(format!("ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {}", t, c.name, d)),
+                        None => out.push(format!("ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT", t, c.name)),
+                    }
+                }
+            }
+        }
+    }
+    for index in to.indexes.iter().filter(|i| index_changed(i, from)) {
+        out.push(create_index(t, index));
+    }
+    out
+}
+
+/// Current UTC time as `YYYYMMDDHHMMSS`, the version format of `sqlx migrate add`.
+fn utc_version() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default() as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // civil-from-days, Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
@@ -0,0 +1,56 @@
+/// One entry of the `NAORM_FIELDS` constant of a derived struct, a column in
+/// `SELECT_SQL` order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    /// Field type without `Option` layers, as written (`chrono::DateTime<Utc>`).
+    pub field_type: &'static str,
+    pub is_option: bool,
+    pub is_auto_increment: bool,
+    pub is_primary_key: bool,
+    /// `default = ...` value, or the inferred default.
+    pub default_value: &'static str,
+    /// Column type in the DDL, empty for `driver = "any"`.
+    pub sql_type: &'static str,
+}
+
+impl FieldInfo {
+    #[doc(hidden)]
+    pub const EMPTY: FieldInfo = FieldInfo {
+        name: "",
+        field_type: "",
+        is_option: false,
+        is_auto_increment: false,
+        is_primary_key: false,
+        default_value: "",
+        sql_type: "",
+    };
+}
+
+/// Implemented by `#[derive(NaormReg)]`. Gives generic code (relations between two
+/// derived types, runtime helpers) access to the generated constants and the
+/// primary key of a value.
+pub trait Model {
+    type Database: sqlx::Database;
+    /// Type of the primary key field, `()` when the struct declares none.
+    type Key;
+    const TABLE: &'static str;
+    const PK: &'static str;
+    const SELECT_SQL: &'static str;
+    /// Column names in `SELECT_SQL` order.
+    const COLUMNS: &'static [&'static str];
+
+    fn key(&self) -> &Self::Key;
+
+    /// Table description used by [`crate::migrate`] and schema verification.
+    fn schema() -> crate::schema::TableSchema;
+
+    /// Decodes a value from columns named `{prefix}{column}`, as selected by the join
+    /// builder.
+    fn from_row_prefixed(
+        row: &<Self::Database as sqlx::Database>::Row,
+        prefix: &str,
+    ) -> Result<Self, sqlx::Error>
+    where
+        Self: Sized;
+}
@@ -0,0 +1,32 @@
+//! Support for `#[derive(NaormProjection)]`: structs reading a subset of the columns
+//! of a model.
+
+/// Field types a projection field can read a model column into: the type of the
+/// model field, or an `Option` of it.
+#[diagnostic::on_unimplemented(
+    message = "projection field of type `{Self}` can not read a model column of type `{T}`",
+    label = "expected `{T}` or `Option<{T}>`"
+)]
+pub trait Compatible<T> {}
+
+impl<T> Compatible<T> for T {}
+
+impl<T> Compatible<T> for Option<T> {}
+
+/// Whether `column` is one of the model's `columns`; a `flatten` field of the model
+/// has a field but no column of its name.
+#[doc(hidden)]
+pub const fn has_column(columns: &[&str], column: &str) -> bool {
+    let mut i = 0;
+    while i < columns.len() {
+        if crate::__private::str_eq(columns[i], column) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Compile-time check of one projection field, called by the derive.
+#[doc(hidden)]
+pub fn compatible<P: Compatible<T>, T>(_: &T) {}
@@ -0,0 +1,239 @@
+//! Table descriptions produced by the derive (`Model::schema()`), shared by the
+//! migration generator and live schema checks.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Backend;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TableSchema {
+    pub name: String,
+    pub backend: Backend,
+    /// The generated `CREATE_TABLE_SQL`.
+    pub create_sql: String,
+    pub columns: Vec<ColumnSchema>,
+    #[serde(default)]
+    pub indexes: Vec<IndexSchema>,
+}
+
+impl TableSchema {
+    pub fn column(&self, name: &str) -> Option<&ColumnSchema> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    /// SQL type as written in the DDL, e.g. `VARCHAR(200)`.
+    pub sql_type: String,
+    pub nullable: bool,
+    pub primary_key: bool,
+    pub auto_increment: bool,
+    /// Default value as it appears in the DDL (already quoted).
+    pub default: Option<String>,
+    /// The full column definition of the `CREATE TABLE` statement.
+    pub definition: String,
+    /// Previous name from `#[naorm_cfg(renamed_from = "...")]`; a migration hint only,
+    /// never stored in snapshots.
+    #[serde(skip)]
+    pub renamed_from: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexSchema {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+/// A column as reported by the database (`PRAGMA table_info`,
+/// `information_schema.columns`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiveColumn {
+    pub name: String,
+    pub sql_type: String,
+    pub nullable: bool,
+    pub default: Option<String>,
+}
+
+impl From<(String, String, String, Option<String>)> for LiveColumn {
+    /// A row of [`introspect_sql`].
+    fn from((name, sql_type, nullable, default): (String, String, String, Option<String>)) -> LiveColumn {
+        LiveColumn {
+            name,
+            sql_type,
+            nullable: nullable.eq_ignore_ascii_case("YES"),
+            default,
+        }
+    }
+}
+
+/// Query returning `(name, type, is_nullable, default)` for the table bound as the
+/// only parameter; `is_nullable` is `YES` or `NO`.
+pub const fn introspect_sql(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Sqlite => {
+            "SELECT name, type, CASE WHEN \"notnull\" = 0 THEN 'YES' ELSE 'NO' END, dflt_value \
+             FROM pragma_table_info(?) ORDER BY cid"
+        }
+        // information_schema columns are binary strings on some MySQL versions
+        Backend::MySql => {
+            "SELECT CAST(COLUMN_NAME AS CHAR), CAST(COLUMN_TYPE AS CHAR), CAST(IS_NULLABLE AS CHAR), \
+             CAST(COLUMN_DEFAULT AS CHAR) FROM information_schema.COLUMNS \
+             WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? ORDER BY ORDINAL_POSITION"
+        }
+        Backend::Postgres => {
+            "SELECT column_name::text, CASE WHEN data_type = 'USER-DEFINED' THEN udt_name::text \
+             WHEN numeric_precision IS NOT NULL AND data_type = 'numeric' \
+             THEN 'numeric(' || numeric_precision || ', ' || numeric_scale || ')' \
+             WHEN character_maximum_length IS NULL THEN data_type::text \
+             ELSE data_type::text || '(' || character_maximum_length || ')' END, is_nullable::text, \
+             column_default::text FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = $1 ORDER BY ordinal_position"
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaIssue {
+    MissingTable,
+    /// A struct field without a column; `FromRow` will fail.
+    MissingColumn { column: String },
+    /// A column unknown to the struct that is `NOT NULL` without default; inserts
+    /// will fail.
+    ExtraRequiredColumn { column: String },
+    TypeMismatch { column: String, expected: String, actual: String },
+    NullabilityMismatch { column: String, expected_nullable: bool, actual_nullable: bool },
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaIssue::MissingTable => write!(f, "table does not exist"),
+            SchemaIssue::MissingColumn { column } => write!(f, "column `{}` is missing", column),
+            SchemaIssue::ExtraRequiredColumn { column } => {
+                write!(f, "column `{}` is NOT NULL without default and unknown to the struct", column)
+            }
+            SchemaIssue::TypeMismatch { column, expected, actual } => {
+                write!(f, "column `{}` is {}, expected {}", column, actual, expected)
+            }
+            SchemaIssue::NullabilityMismatch { column, expected_nullable, .. } => {
+                if *expected_nullable {
+                    write!(f, "column `{}` is NOT NULL, expected nullable", column)
+                } else {
+                    write!(f, "column `{}` is nullable, expected NOT NULL", column)
+                }
+            }
+        }
+    }
+}
+
+/// Result of the generated `verify_schema(executor)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaReport {
+    pub table: String,
+    pub issues: Vec<SchemaIssue>,
+}
+
+impl SchemaReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Compares the expected schema with the columns read from the database.
+    pub fn compare(expected: &TableSchema, live: &[LiveColumn]) -> SchemaReport {
+        let mut issues = Vec::new();
+        if live.is_empty() {
+            issues.push(SchemaIssue::MissingTable);
+        } else {
+            for col in &expected.columns {
+                let Some(actual) = live.iter().find(|c| c.name.eq_ignore_ascii_case(&col.name)) else {
+                    issues.push(SchemaIssue::MissingColumn { column: col.name.clone() });
+                    continue;
+                };
+                let (want, got) = (
+                    normalize_type(expected.backend, &col.sql_type),
+                    normalize_type(expected.backend, &actual.sql_type),
+                );
+                if want != got {
+                    issues.push(SchemaIssue::TypeMismatch {
+                        column: col.name.clone(),
+                        expected: col.sql_type.clone(),
+                        actual: actual.sql_type.clone(),
+                    });
+                }
+                // sqlite reports `INTEGER PRIMARY KEY` as nullable
+                if !col.primary_key && col.nullable != actual.nullable {
+                    issues.push(SchemaIssue::NullabilityMismatch {
+                        column: col.name.clone(),
+                        expected_nullable: col.nullable,
+                        actual_nullable: actual.nullable,
+                    });
+                }
+            }
+            for actual in live {
+                let known = expected.columns.iter().any(|c| c.name.eq_ignore_ascii_case(&actual.name));
+                if !known && !actual.nullable && actual.default.is_none() {
+                    issues.push(SchemaIssue::ExtraRequiredColumn { column: actual.name.clone() });
+                }
+            }
+        }
+        SchemaReport {
+            table: expected.name.clone(),
+            issues,
+        }
+    }
+}
+
+impl fmt::Display for SchemaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return write!(f, "{}: ok", self.table);
+        }
+        write!(f, "{}:", self.table)?;
+        for issue in &self.issues {
+            write!(f, " {};", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Canonical spelling of a column type, so `int8`/`bigint`/`BIGSERIAL` or
+/// `character varying(20)`/`VARCHAR(20)` compare equal.
+pub fn normalize_type(backend: Backend, ty: &str) -> String {
+    let ty = ty.trim().to_lowercase();
+    let (base, args) = match ty.split_once('(') {
+        Some((base, rest)) => (base.trim().to_string(), format!("({}", rest.replace(' ', ""))),
+        None => (ty.clone(), String::new()),
+    };
+    let (base, args) = match backend {
+        // mysql reports integer display widths (`bigint(20)`); `tinyint(1)` is BOOLEAN
+        Backend::MySql if base == "tinyint" && args == "(1)" => ("boolean".to_string(), String::new()),
+        Backend::MySql if base.ends_with("int") => (base, String::new()),
+        Backend::MySql if base.starts_with("int") || base.contains("int ") => {
+            (base.replacen("integer", "int", 1), String::new())
+        }
+        _ => (base, args),
+    };
+    let canonical = match base.as_str() {
+        "int" | "int4" | "integer" | "serial" | "serial4" => "integer",
+        "int8" | "bigint" | "bigserial" | "serial8" => "bigint",
+        "int2" | "smallint" | "smallserial" => "smallint",
+        "character varying" | "varchar" => "varchar",
+        "float8" | "double precision" | "double" => "double",
+        "float4" | "real" => "real",
+        "bool" | "boolean" => "boolean",
+        "decimal" | "numeric" => "numeric",
+        "timestamptz" | "timestamp with time zone" => "timestamptz",
+        "timestamp without time zone" => "timestamp",
+        "time without time zone" => "time",
+        other => other,
+    };
+    // sqlite keeps the declared type, but `INT` and `INTEGER` are the same affinity
+    if backend == Backend::Sqlite && canonical == "bigint" {
+        return "integer".to_string();
+    }
+    format!("{}{}", canonical, args)
+}
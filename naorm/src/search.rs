@@ -0,0 +1,33 @@
+//! Rows returned by the generated `search_hits_query(term)` of `fulltext` structs.
+use sqlx::{ColumnIndex, Database, Decode, FromRow, Row, Type};
+
+use crate::Model;
+
+/// A search match with its rank and the driver's excerpts: FTS5 `snippet()` /
+/// `highlight()` on sqlite, `ts_headline` on postgres, always `None` on mysql. Matched
+/// terms are wrapped in `<b>...</b>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit<M> {
+    pub model: M,
+    /// Higher is better on every driver.
+    pub rank: f64,
+    pub snippet: Option<String>,
+    pub highlight: Option<String>,
+}
+
+impl<'r, M> FromRow<'r, <M::Database as Database>::Row> for Hit<M>
+where
+    M: Model,
+    for<'s> &'s str: ColumnIndex<<M::Database as Database>::Row>,
+    f64: Decode<'r, M::Database> + Type<M::Database>,
+    String: Decode<'r, M::Database> + Type<M::Database>,
+{
+    fn from_row(row: &'r <M::Database as Database>::Row) -> Result<Self, sqlx::Error> {
+        Ok(Hit {
+            model: M::from_row_prefixed(row, "")?,
+            rank: row.try_get("naorm_rank")?,
+            snippet: row.try_get("naorm_snippet")?,
+            highlight: row.try_get("naorm_highlight")?,
+        })
+    }
+}
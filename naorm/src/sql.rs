@@ -0,0 +1,55 @@
+//! Small SQL building helpers for statements the derive can only finish at runtime.
+
+use crate::Backend;
+
+/// One statement in the dialect of each backend; the SQL constants of structs
+/// derived with `driver = "any"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DialectSql {
+    pub sqlite: &'static str,
+    pub mysql: &'static str,
+    pub postgres: &'static str,
+}
+
+impl DialectSql {
+    pub const fn get(&self, backend: Backend) -> &'static str {
+        match backend {
+            Backend::Sqlite => self.sqlite,
+            Backend::MySql => self.mysql,
+            Backend::Postgres => self.postgres,
+        }
+    }
+}
+
+/// Values bound in one `IN (...)` list by the batched relation loaders; larger inputs
+/// are split over several queries, well below the bind limits of every backend
+/// (32766 on sqlite, 65535 on mysql and postgres).
+pub const IN_LIST_CHUNK: usize = 1000;
+
+/// Bind parameter syntax of a backend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaceholderStyle {
+    /// `?` (sqlite, mysql)
+    Question,
+    /// `$1, $2, ...` (postgres)
+    Dollar,
+}
+
+impl PlaceholderStyle {
+    /// Placeholder of the `n`-th (1-based) parameter.
+    pub fn placeholder(self, n: usize) -> String {
+        match self {
+            PlaceholderStyle::Question => "?".to_string(),
+            PlaceholderStyle::Dollar => format!("${}", n),
+        }
+    }
+
+    /// `count` comma separated placeholders starting at parameter `start`, as used in
+    /// `IN (...)` lists.
+    pub fn list(self, start: usize, count: usize) -> String {
+        (start..start + count)
+            .map(|n| self.placeholder(n))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
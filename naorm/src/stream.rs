@@ -0,0 +1,75 @@
+//! Streams behind the generated `stream_all`, `stream_filter`, `stream_chunks` and
+//! `stream_keyset`. `stream_filter` binds its values through a closure, never
+//! formatting them into the SQL.
+//!
+//! ```ignore
+//! use futures_util::TryStreamExt;
+//!
+//! let mut notes = BookNote::stream_all(&pool);
+//! while let Some(note) = notes.try_next().await? {
+//!     export(note)?;
+//! }
+//! let sql = format!("{} WHERE book_id = ?", BookNote::SELECT_SQL);
+//! let notes = BookNote::stream_filter(&sql, |q| q.bind(book_id), &pool);
+//! let batches = naorm::stream::chunks(notes, 100);
+//! ```
+pub use futures_core::Stream;
+use futures_util::{StreamExt, stream};
+use sqlx::{Database, Encode, Executor, FromRow, IntoArguments, Type};
+
+/// `rows` in batches of up to `size`; a batch holding an error yields the error.
+pub fn chunks<'e, T>(
+    rows: impl Stream<Item = Result<T, sqlx::Error>> + Send + 'e,
+    size: usize,
+) -> impl Stream<Item = Result<Vec<T>, sqlx::Error>> + Send + 'e
+where
+    T: Send + 'e,
+{
+    rows.chunks(size.max(1)).map(|batch| batch.into_iter().collect())
+}
+
+/// Batches of up to `batch_size` rows read by key: `first_sql` selects the first batch
+/// (`... ORDER BY key LIMIT ?`), `next_sql` the rows after the last key read
+/// (`... WHERE key > ? ORDER BY key LIMIT ?`). Every batch is its own query, so no
+/// cursor stays open between batches.
+pub fn keyset<'e, DB, M, K, E>(
+    executor: E,
+    first_sql: String,
+    next_sql: String,
+    batch_size: usize,
+    key: fn(&M) -> K,
+) -> impl Stream<Item = Result<Vec<M>, sqlx::Error>> + 'e
+where
+    DB: Database,
+    E: Executor<'e, Database = DB> + Copy + 'e,
+    M: for<'r> FromRow<'r, DB::Row> + Send + Unpin + 'e,
+    K: for<'q> Encode<'q, DB> + Type<DB> + Send + 'e,
+    i64: for<'q> Encode<'q, DB> + Type<DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+{
+    let limit = batch_size.max(1);
+    // `None` once done, `Some(None)` before the first batch
+    stream::try_unfold(Some(None::<K>), move |state| {
+        let (first_sql, next_sql) = (first_sql.clone(), next_sql.clone());
+        async move {
+            let Some(after) = state else {
+                return Ok(None);
+            };
+            let rows: Vec<M> = match after {
+                None => sqlx::query_as(&first_sql).bind(limit as i64).fetch_all(executor).await?,
+                Some(key) => {
+                    sqlx::query_as(&next_sql)
+                        .bind(key)
+                        .bind(limit as i64)
+                        .fetch_all(executor)
+                        .await?
+                }
+            };
+            if rows.is_empty() {
+                return Ok(None);
+            }
+            let next = (rows.len() == limit).then(|| rows.last().map(key));
+            Ok(Some((rows, next)))
+        }
+    })
+}
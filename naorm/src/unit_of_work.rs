@@ -0,0 +1,222 @@
+//! Writes of several derived types collected and flushed in one transaction.
+//!
+//! ```ignore
+//! let mut work = naorm::UnitOfWork::new();
+//! let book = Book { id: Uuid::new_v4(), .. };
+//! work.insert(BookNote { book_id: book.id, .. })?; // written after `book`: `belongs_to = "Book"`
+//! work.insert(book)?;
+//! work.delete(old_note);
+//! work.commit(&pool).await?;
+//! ```
+//!
+//! Inserts run first and updates second, both parents first along the `belongs_to`
+//! relations of the pending types; deletes run last, children first. A row can be moved
+//! to a new parent and its old parent deleted in one unit, but a key freed by a delete
+//! can not be inserted again in the same unit: replace such a row with `update`. Every
+//! value is validated before the first statement. [`UnitOfWork::commit`] takes
+//! anything that can begin a transaction: given a pool it commits a transaction, given
+//! `&mut tx` it works in a savepoint of `tx`, which is rolled back alone when the
+//! nested work fails.
+//!
+//! The values are moved into the unit and nothing is read back, so the rows queued
+//! with a parent could not learn a key the database generates for it:
+//! [`UnitOfWork::insert`] rejects structs with an `auto_increment` key with
+//! [`Error::GeneratedKey`]. Give parents and children keys before queueing them
+//! (explicit ids, UUIDs), or insert a row with a generated key on its own first.
+//!
+//! Structs for several drivers implement [`Persist`] for each of them and can be
+//! queued in a unit of work on any of those databases; `driver = "any"` structs
+//! can not.
+use std::future::Future;
+use std::pin::Pin;
+
+use sqlx::query::Query;
+use sqlx::{Acquire, Database, Executor, IntoArguments};
+
+use crate::{Error, Model, ValidationErrors};
+
+type Args<'q, DB> = <DB as Database>::Arguments<'q>;
+type Execution<'c, DB> = Pin<Box<dyn Future<Output = Result<<DB as Database>::QueryResult, sqlx::Error>> + Send + 'c>>;
+type Write<DB> = Box<dyn for<'c> FnOnce(&'c mut <DB as Database>::Connection) -> Execution<'c, DB> + Send>;
+
+/// Implemented by `#[derive(NaormReg)]` for each concrete driver of the struct: the
+/// writes a [`UnitOfWork`] on `DB` runs.
+pub trait Persist<DB: Database>: Model {
+    /// Tables referenced through `belongs_to`, written before this one.
+    const REFERENCES: &'static [&'static str];
+    /// Whether the database generates the key (`auto_increment`).
+    const GENERATED_KEY: bool;
+
+    fn validate(&self) -> Result<(), ValidationErrors>;
+    fn insert_query<'q>(&'q mut self) -> Query<'q, DB, Args<'q, DB>>;
+    fn update_query<'q>(&'q mut self) -> Query<'q, DB, Args<'q, DB>>;
+    fn delete_query<'q>(&'q self) -> Query<'q, DB, Args<'q, DB>>;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Insert,
+    Update,
+    Delete,
+}
+
+struct Pending<DB: Database> {
+    kind: Kind,
+    table: &'static str,
+    references: &'static [&'static str],
+    validation: Option<ValidationErrors>,
+    write: Write<DB>,
+}
+
+/// Pending inserts, updates and deletes on `DB`; see the module docs.
+pub struct UnitOfWork<DB: Database> {
+    pending: Vec<Pending<DB>>,
+}
+
+impl<DB: Database> Default for UnitOfWork<DB> {
+    fn default() -> Self {
+        UnitOfWork { pending: Vec::new() }
+    }
+}
+
+impl<DB> UnitOfWork<DB>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> Args<'q, DB>: IntoArguments<'q, DB>,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues the insertion of `value`; fails for structs whose key the database
+    /// generates, which the rows queued with `value` could not reference.
+    pub fn insert<M>(&mut self, value: M) -> Result<(), Error>
+    where
+        M: Persist<DB> + Send + 'static,
+    {
+        if <M as Persist<DB>>::GENERATED_KEY {
+            return Err(Error::GeneratedKey(M::TABLE));
+        }
+        self.push(Kind::Insert, value, |mut value, conn| {
+            Box::pin(async move { value.insert_query().execute(conn).await })
+        });
+        Ok(())
+    }
+
+    /// Updates the row of `value` by primary key.
+    pub fn update<M>(&mut self, value: M)
+    where
+        M: Persist<DB> + Send + 'static,
+    {
+        self.push(Kind::Update, value, |mut value, conn| {
+            Box::pin(async move { value.update_query().execute(conn).await })
+        });
+    }
+
+    /// Deletes the row of `value` by primary key.
+    pub fn delete<M>(&mut self, value: M)
+    where
+        M: Persist<DB> + Send + 'static,
+    {
+        self.push(Kind::Delete, value, |value, conn| {
+            Box::pin(async move { value.delete_query().execute(conn).await })
+        });
+    }
+
+    fn push<M>(
+        &mut self,
+        kind: Kind,
+        value: M,
+        write: for<'c> fn(M, &'c mut DB::Connection) -> Execution<'c, DB>,
+    ) where
+        M: Persist<DB> + Send + 'static,
+    {
+        // deleted rows are not validated
+        let validation = match kind {
+            Kind::Delete => None,
+            _ => Persist::<DB>::validate(&value).err(),
+        };
+        self.pending.push(Pending {
+            kind,
+            table: M::TABLE,
+            references: <M as Persist<DB>>::REFERENCES,
+            validation,
+            write: Box::new(move |conn| write(value, conn)),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Runs the pending writes in a transaction of `conn`, a savepoint when `conn` is
+    /// a transaction, and commits it. Nothing is written when a value fails
+    /// validation; a failing statement rolls the whole work back.
+    pub async fn commit<'a, A>(self, conn: A) -> Result<DB::QueryResult, Error>
+    where
+        A: Acquire<'a, Database = DB>,
+    {
+        if let Some(errors) = self.pending.iter().find_map(|p| p.validation.as_ref()) {
+            return Err(Error::Validation(errors.clone()));
+        }
+        let mut tx = conn.begin().await?;
+        let mut result = DB::QueryResult::default();
+        for pending in self.ordered() {
+            result.extend(Some((pending.write)(&mut *tx).await?));
+        }
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    // inserts and updates parents first, then deletes children first; the order of
+    // the calls is kept within a table
+    fn ordered(self) -> Vec<Pending<DB>> {
+        let tables = dependency_order(&self.pending);
+        let mut pending: Vec<Option<Pending<DB>>> = self.pending.into_iter().map(Some).collect();
+        let mut out = Vec::with_capacity(pending.len());
+        let mut take = |kind: Kind, table: &str| {
+            for slot in pending.iter_mut() {
+                if slot.as_ref().is_some_and(|p| p.kind == kind && p.table == table) {
+                    out.extend(slot.take());
+                }
+            }
+        };
+        for kind in [Kind::Insert, Kind::Update] {
+            for table in &tables {
+                take(kind, table);
+            }
+        }
+        for table in tables.iter().rev() {
+            take(Kind::Delete, table);
+        }
+        out
+    }
+}
+
+// tables of `pending`, each after the pending tables it references; tables in a
+// reference cycle keep the order they were first used in
+fn dependency_order<DB: Database>(pending: &[Pending<DB>]) -> Vec<&'static str> {
+    let mut tables: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    for p in pending {
+        if !tables.iter().any(|(t, _)| *t == p.table) {
+            tables.push((p.table, p.references));
+        }
+    }
+    let mut ordered: Vec<&'static str> = Vec::with_capacity(tables.len());
+    while ordered.len() < tables.len() {
+        let ready = tables.iter().find(|(table, references)| {
+            !ordered.contains(table)
+                && references
+                    .iter()
+                    .all(|r| r == table || ordered.contains(r) || !tables.iter().any(|(t, _)| t == r))
+        });
+        let (table, _) = ready.or_else(|| tables.iter().find(|(t, _)| !ordered.contains(t))).unwrap();
+        ordered.push(table);
+    }
+    ordered
+}
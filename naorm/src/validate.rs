@@ -0,0 +1,164 @@
+//! Validation rules used by the generated `validate(&self)`.
+//!
+//! Each helper records a [`ValidationError`] instead of returning early, so one call
+//! to `validate()` reports every invalid field.
+use std::fmt;
+
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationErrorKind {
+    MaxLen { max: usize, actual: usize },
+    Min { min: String },
+    Max { max: String },
+    Regex { pattern: String },
+    NotEmpty,
+    Custom(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub kind: ValidationErrorKind,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ValidationErrorKind::MaxLen { max, actual } => {
+                write!(f, "{}: length {} exceeds {}", self.field, actual, max)
+            }
+            ValidationErrorKind::Min { min } => write!(f, "{}: must be >= {}", self.field, min),
+            ValidationErrorKind::Max { max } => write!(f, "{}: must be <= {}", self.field, max),
+            ValidationErrorKind::Regex { pattern } => {
+                write!(f, "{}: does not match `{}`", self.field, pattern)
+            }
+            ValidationErrorKind::NotEmpty => write!(f, "{}: must not be empty", self.field),
+            ValidationErrorKind::Custom(msg) => write!(f, "{}: {}", self.field, msg),
+        }
+    }
+}
+
+/// All failed rules of one value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationErrors(Vec<ValidationError>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, field: &'static str, kind: ValidationErrorKind) {
+        self.0.push(ValidationError { field, kind });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.0
+    }
+
+    /// Errors reported for `field`.
+    pub fn field(&self, field: &str) -> impl Iterator<Item = &ValidationError> {
+        self.0.iter().filter(move |e| e.field == field)
+    }
+
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.0.is_empty() { Ok(()) } else { Err(self) }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Anything `max_len`/`not_empty` can measure.
+pub trait Length {
+    fn length(&self) -> usize;
+}
+
+impl Length for str {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl Length for String {
+    fn length(&self) -> usize {
+        self.as_str().length()
+    }
+}
+
+impl<T> Length for [T] {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> Length for Vec<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: Length + ?Sized> Length for &T {
+    fn length(&self) -> usize {
+        (**self).length()
+    }
+}
+
+impl<T: Length + ToOwned + ?Sized> Length for std::borrow::Cow<'_, T> {
+    fn length(&self) -> usize {
+        self.as_ref().length()
+    }
+}
+
+pub fn max_len<T: Length + ?Sized>(errors: &mut ValidationErrors, field: &'static str, value: &T, max: usize) {
+    let actual = value.length();
+    if actual > max {
+        errors.push(field, ValidationErrorKind::MaxLen { max, actual });
+    }
+}
+
+pub fn min<T: PartialOrd + fmt::Display>(errors: &mut ValidationErrors, field: &'static str, value: &T, min: T) {
+    if *value < min {
+        errors.push(field, ValidationErrorKind::Min { min: min.to_string() });
+    }
+}
+
+pub fn max<T: PartialOrd + fmt::Display>(errors: &mut ValidationErrors, field: &'static str, value: &T, max: T) {
+    if *value > max {
+        errors.push(field, ValidationErrorKind::Max { max: max.to_string() });
+    }
+}
+
+pub fn regex<T: AsRef<str> + ?Sized>(errors: &mut ValidationErrors, field: &'static str, value: &T, re: &Regex) {
+    if !re.is_match(value.as_ref()) {
+        errors.push(field, ValidationErrorKind::Regex { pattern: re.as_str().to_string() });
+    }
+}
+
+pub fn not_empty<T: Length + ?Sized>(errors: &mut ValidationErrors, field: &'static str, value: &T) {
+    if value.length() == 0 {
+        errors.push(field, ValidationErrorKind::NotEmpty);
+    }
+}
+
+/// Records the outcome of a `validate_with` function.
+pub fn custom(errors: &mut ValidationErrors, field: &'static str, result: Result<(), String>) {
+    if let Err(msg) = result {
+        errors.push(field, ValidationErrorKind::Custom(msg));
+    }
+}
@@ -0,0 +1,100 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::DeriveInput;
+
+use crate::table_create::Column;
+
+/// `{Struct}Columns`, the typed handles of the columns of the struct (`flatten`
+/// fields have none), returned by `Struct::columns()`.
+pub fn columns_struct(naorm: &TokenStream, input: &DeriveInput, columns: &[Column]) -> TokenStream {
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let columns_ident = format_ident!("{}Columns", ident);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let handles: Vec<(syn::Ident, TokenStream)> = columns
+        .iter()
+        .filter(|c| !c.attrs.flatten)
+        .map(|col| {
+            let field = format_ident!("{}", col.name);
+            let value = col.attrs.wrapped.as_ref().unwrap_or(&col.ty.inner);
+            let handle = if col.is_option {
+                quote! { #naorm::Column<#ident #ty_generics, #value, Option<#value>> }
+            } else {
+                quote! { #naorm::Column<#ident #ty_generics, #value> }
+            };
+            (field, handle)
+        })
+        .collect();
+    let fields = handles.iter().map(|(field, handle)| quote! { pub #field: #handle });
+    let values = handles.iter().map(|(field, handle)| {
+        let name = field.to_string();
+        quote! { #field: <#handle>::new(#name) }
+    });
+    let doc = format!("Typed column handles of [`{}`], from `{}::columns()`.", ident, ident);
+    quote! {
+        #[doc = #doc]
+        #[derive(Clone, Copy, Debug)]
+        #vis struct #columns_ident #impl_generics #where_clause {
+            #(#fields,)*
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub const fn columns() -> #columns_ident #ty_generics {
+                #columns_ident {
+                    #(#values,)*
+                }
+            }
+        }
+    }
+}
+
+/// `count_query()`, `sum_query(column)`, ... of the generated impl, building the
+/// queries of `naorm::aggregate` on the database of the `Model` impl.
+pub fn methods(naorm: &TokenStream) -> TokenStream {
+    let db = quote! { <Self as #naorm::Model>::Database };
+    let agg = quote! { #naorm::aggregate };
+    quote! {
+        /// `SELECT COUNT(*)` of the table.
+        pub fn count_query() -> #agg::Aggregate<Self, i64>
+        where
+            Self: #naorm::Model,
+        {
+            #agg::count()
+        }
+        /// `SELECT SUM(column)` of the table, typed as the database computes it.
+        pub fn sum_query<NaormT, NaormK>(column: #naorm::Column<Self, NaormT, NaormK>) -> #agg::Aggregate<Self, Option<<NaormT as #agg::Sum<#db>>::Output>>
+        where
+            Self: #naorm::Model,
+            NaormT: #agg::Sum<#db>,
+        {
+            #agg::sum(column)
+        }
+        /// `SELECT AVG(column)` of the table, typed as the database computes it.
+        pub fn avg_query<NaormT, NaormK>(column: #naorm::Column<Self, NaormT, NaormK>) -> #agg::Aggregate<Self, Option<<NaormT as #agg::Avg<#db>>::Output>>
+        where
+            Self: #naorm::Model,
+            NaormT: #agg::Avg<#db>,
+        {
+            #agg::avg(column)
+        }
+        pub fn min_query<NaormT, NaormK>(column: #naorm::Column<Self, NaormT, NaormK>) -> #agg::Aggregate<Self, Option<NaormT>>
+        where
+            Self: #naorm::Model,
+        {
+            #agg::min(column)
+        }
+        pub fn max_query<NaormT, NaormK>(column: #naorm::Column<Self, NaormT, NaormK>) -> #agg::Aggregate<Self, Option<NaormT>>
+        where
+            Self: #naorm::Model,
+        {
+            #agg::max(column)
+        }
+        /// Rows grouped by `column`, e.g. `group_by(column).count()`.
+        pub fn group_by<NaormT, NaormK>(column: #naorm::Column<Self, NaormT, NaormK>) -> #agg::GroupBy<Self, NaormK>
+        where
+            Self: #naorm::Model,
+        {
+            #agg::group_by(column)
+        }
+    }
+}
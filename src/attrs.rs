@@ -0,0 +1,431 @@
+use proc_macro_error::emit_error;
+use quote::ToTokens;
+use syn::meta::ParseNestedMeta;
+use syn::{Attribute, Expr, LitInt, LitStr, Token};
+
+use crate::driver::Driver;
+
+/// `default = <literal>` or `default_sql = "<expression>"` of a field.
+#[derive(Clone)]
+pub enum DefaultValue {
+    Str(LitStr),
+    /// Integer or float literal, possibly negative.
+    Number(Expr),
+    Bool(bool),
+    /// SQL expression evaluated by the database, e.g. `CURRENT_TIMESTAMP`.
+    Sql(String),
+}
+
+impl DefaultValue {
+    fn parse(expr: Expr) -> syn::Result<DefaultValue> {
+        match &expr {
+            Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s), ..
+            }) => Ok(DefaultValue::Str(s.clone())),
+            Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Bool(b), ..
+            }) => Ok(DefaultValue::Bool(b.value)),
+            _ => numeric_expr(expr)
+                .map(DefaultValue::Number)
+                .map_err(|e| syn::Error::new(e.span(), "expected a string, number or bool literal")),
+        }
+    }
+
+    /// The value as listed in `NAORM_FIELDS`.
+    pub fn text(&self) -> String {
+        match self {
+            DefaultValue::Str(s) => s.value(),
+            DefaultValue::Number(n) => expr_sql(n),
+            DefaultValue::Bool(b) => b.to_string(),
+            DefaultValue::Sql(sql) => sql.clone(),
+        }
+    }
+}
+
+/// Column type given with `sql_type = "..."` (every driver) and/or
+/// `sql_type(postgres = "...", mysql = "...")`.
+#[derive(Clone, Default)]
+pub struct SqlTypeOverride {
+    pub all: Option<String>,
+    pub per_driver: Vec<(Driver, String)>,
+}
+
+impl SqlTypeOverride {
+    pub fn get(&self, driver: Driver) -> Option<&str> {
+        self.per_driver
+            .iter()
+            .find(|(d, _)| *d == driver)
+            .map(|(_, ty)| ty.as_str())
+            .or(self.all.as_deref())
+    }
+
+    fn parse(&mut self, meta: &ParseNestedMeta) -> syn::Result<()> {
+        if meta.input.peek(Token![=]) {
+            self.all = Some(meta.value()?.parse::<LitStr>()?.value());
+            return Ok(());
+        }
+        meta.parse_nested_meta(|inner| {
+            let name = inner.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+            let Some(driver) = Driver::parse(&name) else {
+                return Err(inner.error("expected `sqlite`, `mysql` or `postgres`"));
+            };
+            let ty = inner.value()?.parse::<LitStr>()?.value();
+            self.per_driver.retain(|(d, _)| *d != driver);
+            self.per_driver.push((driver, ty));
+            Ok(())
+        })
+    }
+}
+
+/// A struct-level `has_many(Child, foreign_key = "...")` / `has_one(...)` declaration.
+#[derive(Clone)]
+pub struct HasRelation {
+    pub many: bool,
+    pub target: syn::Path,
+    pub foreign_key: String,
+    pub name: Option<String>,
+}
+
+/// A struct-level `many_to_many(Target, through = "...", local = "...", foreign = "...")`
+/// declaration.
+#[derive(Clone)]
+pub struct ManyToMany {
+    pub target: syn::Path,
+    pub through: String,
+    pub local: String,
+    pub foreign: String,
+    pub name: Option<String>,
+}
+
+/// A struct-level `index(name = "...", columns = "a, b", unique)` declaration.
+#[derive(Clone)]
+pub struct IndexDecl {
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+/// Options collected from the `#[naorm_cfg(...)]` attributes of the struct.
+#[derive(Default)]
+pub struct StructAttrs {
+    pub table_name: Option<String>,
+    pub db_name: Option<String>,
+    pub table_type: Option<String>,
+    pub driver: Option<LitStr>,
+    /// `drivers = "sqlite, postgres"`
+    pub drivers: Option<LitStr>,
+    pub relations: Vec<HasRelation>,
+    pub many_to_many: Vec<ManyToMany>,
+    pub indexes: Vec<IndexDecl>,
+    /// `impl Default` through `new_default()`.
+    pub impl_default: bool,
+}
+
+impl StructAttrs {
+    pub fn parse(attrs: &[Attribute]) -> StructAttrs {
+        let mut out = StructAttrs::default();
+        for attr in attrs {
+            if !attr.path().is_ident("naorm_cfg") {
+                continue;
+            }
+            let res = attr.parse_nested_meta(|meta| {
+                let key = meta
+                    .path
+                    .get_ident()
+                    .map(|i| i.to_string())
+                    .unwrap_or_default();
+                match key.as_str() {
+                    "table_name" => out.table_name = Some(meta.value()?.parse::<LitStr>()?.value()),
+                    "db_name" => out.db_name = Some(meta.value()?.parse::<LitStr>()?.value()),
+                    "table_type" => out.table_type = Some(meta.value()?.parse::<LitStr>()?.value()),
+                    "driver" => out.driver = Some(meta.value()?.parse()?),
+                    "drivers" => out.drivers = Some(meta.value()?.parse()?),
+                    "has_many" => out.relations.push(parse_has(&meta, true)?),
+                    "has_one" => out.relations.push(parse_has(&meta, false)?),
+                    "many_to_many" => out.many_to_many.push(parse_many_to_many(&meta)?),
+                    "index" => out.indexes.push(parse_index(&meta)?),
+                    "default" => out.impl_default = true,
+                    _ => return Err(meta.error(format!("unknown naorm_cfg option `{}`", key))),
+                }
+                Ok(())
+            });
+            if let Err(e) = res {
+                emit_error!(e.span(), "{}", e);
+            }
+        }
+        out
+    }
+}
+
+// `(Target, key = "value", ...)` of a relation declaration
+fn parse_relation_args(meta: &ParseNestedMeta) -> syn::Result<(syn::Path, Vec<(syn::Ident, LitStr)>)> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+    let target: syn::Path = content.parse()?;
+    let mut args = Vec::new();
+    while !content.is_empty() {
+        content.parse::<Token![,]>()?;
+        if content.is_empty() {
+            break;
+        }
+        let key: syn::Ident = content.parse()?;
+        content.parse::<Token![=]>()?;
+        args.push((key, content.parse()?));
+    }
+    Ok((target, args))
+}
+
+fn required(meta: &ParseNestedMeta, value: Option<String>, key: &str) -> syn::Result<String> {
+    value.ok_or_else(|| meta.error(format!("missing `{} = \"...\"`", key)))
+}
+
+// `has_many(Child, foreign_key = "parent_id", name = "children")`
+fn parse_has(meta: &ParseNestedMeta, many: bool) -> syn::Result<HasRelation> {
+    let (target, args) = parse_relation_args(meta)?;
+    let mut foreign_key = None;
+    let mut name = None;
+    for (key, value) in args {
+        match key.to_string().as_str() {
+            "foreign_key" => foreign_key = Some(value.value()),
+            "name" => name = Some(value.value()),
+            other => {
+                return Err(syn::Error::new(key.span(), format!("unknown relation option `{}`", other)));
+            }
+        }
+    }
+    Ok(HasRelation {
+        many,
+        target,
+        foreign_key: required(meta, foreign_key, "foreign_key")?,
+        name,
+    })
+}
+
+// `many_to_many(Tag, through = "note_tag", local = "note_id", foreign = "tag_id")`
+fn parse_many_to_many(meta: &ParseNestedMeta) -> syn::Result<ManyToMany> {
+    let (target, args) = parse_relation_args(meta)?;
+    let (mut through, mut local, mut foreign, mut name) = (None, None, None, None);
+    for (key, value) in args {
+        match key.to_string().as_str() {
+            "through" => through = Some(value.value()),
+            "local" => local = Some(value.value()),
+            "foreign" => foreign = Some(value.value()),
+            "name" => name = Some(value.value()),
+            other => {
+                return Err(syn::Error::new(key.span(), format!("unknown relation option `{}`", other)));
+            }
+        }
+    }
+    Ok(ManyToMany {
+        target,
+        through: required(meta, through, "through")?,
+        local: required(meta, local, "local")?,
+        foreign: required(meta, foreign, "foreign")?,
+        name,
+    })
+}
+
+// `index(name = "book_note_lookup", columns = "book_id, color", unique)`
+fn parse_index(meta: &ParseNestedMeta) -> syn::Result<IndexDecl> {
+    let mut index = IndexDecl {
+        name: None,
+        columns: Vec::new(),
+        unique: false,
+    };
+    meta.parse_nested_meta(|inner| {
+        let key = inner.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+        match key.as_str() {
+            "name" => index.name = Some(inner.value()?.parse::<LitStr>()?.value()),
+            "columns" => {
+                let lit: LitStr = inner.value()?.parse()?;
+                index.columns = lit
+                    .value()
+                    .split(',')
+                    .map(|c| c.trim().to_string())
+                    .filter(|c| !c.is_empty())
+                    .collect();
+                if index.columns.is_empty() {
+                    return Err(syn::Error::new(lit.span(), "expected at least one column"));
+                }
+            }
+            "unique" => index.unique = true,
+            _ => return Err(inner.error(format!("unknown index option `{}`", key))),
+        }
+        Ok(())
+    })?;
+    if index.columns.is_empty() {
+        return Err(meta.error("missing `columns = \"...\"`"));
+    }
+    Ok(index)
+}
+
+/// Options collected from the `#[naorm_cfg(...)]` attributes of a single field.
+#[derive(Clone, Default)]
+pub struct FieldAttrs {
+    pub primary_key: bool,
+    pub auto_increment: bool,
+    pub default: Option<DefaultValue>,
+    // validation rules
+    pub max_len: Option<usize>,
+    pub min: Option<Expr>,
+    pub max: Option<Expr>,
+    pub regex: Option<LitStr>,
+    pub not_empty: bool,
+    pub validate_with: Option<syn::Path>,
+    // relations
+    pub belongs_to: Option<syn::Path>,
+    pub references: Option<(String, String)>,
+    pub on_delete: Option<String>,
+    pub on_update: Option<String>,
+    // indexes
+    pub index: bool,
+    pub unique: bool,
+    pub fulltext: bool,
+    // `#[derive(NaormEnum)]` type
+    pub is_enum: bool,
+    // serde value stored through `sqlx::types::Json`
+    pub json: bool,
+    // explicit column type
+    pub sql_type: SqlTypeOverride,
+    // newtypes bound and decoded through their single field, `transparent = "i64"`
+    // names the wrapped type for the column type
+    pub transparent: bool,
+    pub wrapped: Option<syn::Type>,
+    // user conversions: `fn(&T) -> V` for binds, `fn(V) -> Result<T, E>` for decoding
+    pub bind_with: Option<syn::Path>,
+    pub decode_with: Option<syn::Path>,
+    // `NaormColumns` type whose columns replace the field, named `{prefix}{column}`
+    pub flatten: bool,
+    pub prefix: Option<String>,
+    // migrations
+    pub renamed_from: Option<String>,
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute]) -> FieldAttrs {
+        let mut out = FieldAttrs::default();
+        for attr in attrs {
+            if !attr.path().is_ident("naorm_cfg") {
+                continue;
+            }
+            let res = attr.parse_nested_meta(|meta| {
+                let key = meta
+                    .path
+                    .get_ident()
+                    .map(|i| i.to_string())
+                    .unwrap_or_default();
+                match key.as_str() {
+                    "primary_key" => out.primary_key = true,
+                    "auto_increment" => out.auto_increment = true,
+                    "default" => out.default = Some(DefaultValue::parse(meta.value()?.parse()?)?),
+                    "default_sql" => {
+                        out.default = Some(DefaultValue::Sql(meta.value()?.parse::<LitStr>()?.value()))
+                    }
+                    "max_len" => out.max_len = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?),
+                    "min" => out.min = Some(numeric_expr(meta.value()?.parse()?)?),
+                    "max" => out.max = Some(numeric_expr(meta.value()?.parse()?)?),
+                    "regex" => {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        if let Err(e) = regex::Regex::new(&lit.value()) {
+                            return Err(syn::Error::new(lit.span(), format!("invalid regex: {}", e)));
+                        }
+                        out.regex = Some(lit);
+                    }
+                    "not_empty" => out.not_empty = true,
+                    "validate_with" => {
+                        out.validate_with = Some(meta.value()?.parse::<LitStr>()?.parse()?)
+                    }
+                    "belongs_to" => out.belongs_to = Some(meta.value()?.parse::<LitStr>()?.parse()?),
+                    "references" => {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        out.references = Some(parse_references(&lit)?);
+                    }
+                    "on_delete" => out.on_delete = Some(fk_action(meta.value()?.parse()?)?),
+                    "on_update" => out.on_update = Some(fk_action(meta.value()?.parse()?)?),
+                    "index" => out.index = true,
+                    "unique" => out.unique = true,
+                    "fulltext" => out.fulltext = true,
+                    "enum" => out.is_enum = true,
+                    "json" => out.json = true,
+                    "sql_type" => out.sql_type.parse(&meta)?,
+                    "transparent" => {
+                        out.transparent = true;
+                        if meta.input.peek(Token![=]) {
+                            out.wrapped = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                        }
+                    }
+                    "bind_with" => out.bind_with = Some(meta.value()?.parse::<LitStr>()?.parse()?),
+                    "decode_with" => out.decode_with = Some(meta.value()?.parse::<LitStr>()?.parse()?),
+                    "flatten" => out.flatten = true,
+                    "prefix" => out.prefix = Some(meta.value()?.parse::<LitStr>()?.value()),
+                    "renamed_from" => {
+                        out.renamed_from = Some(meta.value()?.parse::<LitStr>()?.value())
+                    }
+                    _ => return Err(meta.error(format!("unknown naorm_cfg option `{}`", key))),
+                }
+                Ok(())
+            });
+            if let Err(e) = res {
+                emit_error!(e.span(), "{}", e);
+            }
+        }
+        out
+    }
+}
+
+/// Whether the field carries `#[sqlx(json)]`, which `sqlx::FromRow` needs to decode
+/// `json` fields as well.
+pub fn has_sqlx_json(attrs: &[Attribute]) -> bool {
+    attrs.iter().filter(|a| a.path().is_ident("sqlx")).any(|a| match &a.meta {
+        syn::Meta::List(list) => list
+            .tokens
+            .clone()
+            .into_iter()
+            .any(|t| matches!(t, proc_macro2::TokenTree::Ident(i) if i == "json")),
+        _ => false,
+    })
+}
+
+// `table(column)`
+fn parse_references(lit: &LitStr) -> syn::Result<(String, String)> {
+    let v = lit.value();
+    if let Some((table, rest)) = v.split_once('(')
+        && let Some(col) = rest.strip_suffix(')')
+    {
+        return Ok((table.trim().to_string(), col.trim().to_string()));
+    }
+    Err(syn::Error::new(lit.span(), "expected `table(column)`"))
+}
+
+fn fk_action(lit: LitStr) -> syn::Result<String> {
+    let action = lit.value().trim().to_uppercase().replace('_', " ");
+    match action.as_str() {
+        "CASCADE" | "RESTRICT" | "SET NULL" | "SET DEFAULT" | "NO ACTION" => Ok(action),
+        _ => Err(syn::Error::new(
+            lit.span(),
+            "expected one of cascade, restrict, set_null, set_default, no_action",
+        )),
+    }
+}
+
+// accepts `10`, `-10`, `0.5` and `-0.5`
+fn numeric_expr(expr: Expr) -> syn::Result<Expr> {
+    let ok = match &expr {
+        Expr::Lit(l) => matches!(l.lit, syn::Lit::Int(_) | syn::Lit::Float(_)),
+        Expr::Unary(u) => {
+            matches!(u.op, syn::UnOp::Neg(_))
+                && matches!(&*u.expr, Expr::Lit(l) if matches!(l.lit, syn::Lit::Int(_) | syn::Lit::Float(_)))
+        }
+        _ => false,
+    };
+    if ok {
+        Ok(expr)
+    } else {
+        Err(syn::Error::new_spanned(expr, "expected a numeric literal"))
+    }
+}
+
+/// Renders a numeric literal expression for use in SQL, e.g. `- 5` -> `-5`.
+pub fn expr_sql(expr: &Expr) -> String {
+    expr.to_token_stream().to_string().replace(' ', "")
+}
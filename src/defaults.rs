@@ -0,0 +1,137 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::emit_error;
+use quote::{format_ident, quote};
+
+use crate::attrs::DefaultValue;
+use crate::table_create::Column;
+
+// `1` -> `1.0`, so the literal fits a float field
+fn float_literal(text: &str) -> TokenStream {
+    let text = if text.contains(['.', 'e', 'E']) {
+        text.to_string()
+    } else {
+        format!("{}.0", text)
+    };
+    text.parse().unwrap_or_default()
+}
+
+/// Rust value of the column default without the `Option` layers, and the bound
+/// the value needs (checked when `new_default` is used, not when it is generated).
+fn rust_value(naorm: &TokenStream, col: &Column) -> (TokenStream, Option<TokenStream>) {
+    let ty = col.attrs.wrapped.as_ref().unwrap_or(&col.ty.inner);
+    let fallback = (quote! { ::core::default::Default::default() }, None);
+    let from_str = |text: String| {
+        let name = &col.name;
+        (
+            quote! {
+                match <#ty as ::core::str::FromStr>::from_str(#text) {
+                    Ok(v) => v,
+                    Err(_) => panic!("invalid default for `{}`", #name),
+                }
+            },
+            Some(quote! { for<'naorm> #ty: ::core::str::FromStr }),
+        )
+    };
+    let mismatch = |span: proc_macro2::Span, what: &str| {
+        emit_error!(span, "a {} default does not fit the field type `{}`", what, col.ty.full);
+        fallback.clone()
+    };
+    match col.attrs.default.as_ref() {
+        // computed by the database
+        None | Some(DefaultValue::Sql(_)) => fallback.clone(),
+        Some(DefaultValue::Str(lit)) if col.attrs.json => {
+            let name = &col.name;
+            (
+                quote! {
+                    #naorm::__private::serde_json::from_str(#lit)
+                        .unwrap_or_else(|e| panic!("invalid default for `{}`: {}", #name, e))
+                },
+                None,
+            )
+        }
+        Some(DefaultValue::Str(lit)) if col.is_text() => (quote! { ::core::convert::Into::into(#lit) }, None),
+        // `default = "0"` on a number or bool field
+        Some(DefaultValue::Str(lit)) if col.is_integer() || col.is_float() || col.ty_name == "bool" => {
+            let value = lit.value();
+            let ok = if col.is_integer() {
+                value.parse::<i128>().is_ok()
+            } else if col.is_float() {
+                value.parse::<f64>().is_ok()
+            } else {
+                value == "true" || value == "false"
+            };
+            if !ok {
+                return mismatch(lit.span(), "string");
+            }
+            let value = if col.is_float() {
+                float_literal(&value)
+            } else {
+                value.parse().unwrap_or_default()
+            };
+            (value, None)
+        }
+        Some(DefaultValue::Str(lit)) => from_str(lit.value()),
+        Some(DefaultValue::Number(n)) => {
+            let text = crate::attrs::expr_sql(n);
+            let span = syn::spanned::Spanned::span(n);
+            if col.is_integer() {
+                if text.contains('.') {
+                    return mismatch(span, "float");
+                }
+                (quote! { #n }, None)
+            } else if col.is_float() {
+                (float_literal(&text), None)
+            } else if col.is_text() || col.ty_name == "bool" || col.attrs.json {
+                mismatch(span, "number")
+            } else {
+                from_str(text)
+            }
+        }
+        Some(DefaultValue::Bool(b)) if col.ty_name == "bool" => (quote! { #b }, None),
+        Some(DefaultValue::Bool(_)) => mismatch(proc_macro2::Span::call_site(), "bool"),
+    }
+}
+
+/// The generated `new_default()`.
+pub fn new_default_tokens(naorm: &TokenStream, columns: &[Column]) -> TokenStream {
+    let mut bounds = Vec::new();
+    let fields = columns.iter().map(|col| {
+        let ident = format_ident!("{}", col.name);
+        let (mut value, bound) = rust_value(naorm, col);
+        let explicit = !matches!(col.attrs.default, None | Some(DefaultValue::Sql(_)));
+        match bound {
+            Some(bound) => bounds.push(bound),
+            // `Option` is always `Default`
+            None if !explicit && !col.is_option => {
+                let ty = &col.ty.inner;
+                bounds.push(quote! { for<'naorm> #ty: ::core::default::Default });
+            }
+            None => {}
+        }
+        if explicit {
+            // `transparent = "i64"`: the default is the wrapped value
+            if col.attrs.wrapped.is_some()
+                && let Some(ctor) = crate::types::ctor_path(&col.ty.inner)
+            {
+                value = quote! { #ctor(#value) };
+            }
+            for _ in 0..col.ty.option_depth {
+                value = quote! { Some(#value) };
+            }
+        }
+        quote! { #ident: #value }
+    });
+    let fields: Vec<TokenStream> = fields.collect();
+    quote! {
+        /// A value holding the column defaults: the `default = ...` of each field,
+        /// `Default::default()` for the others (`default_sql` is up to the database).
+        pub fn new_default() -> Self
+        where
+            #(#bounds,)*
+        {
+            Self {
+                #(#fields,)*
+            }
+        }
+    }
+}
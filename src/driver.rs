@@ -0,0 +1,188 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Database backend selected with `#[naorm_cfg(driver = "...")]`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Driver {
+    Sqlite,
+    MySql,
+    Postgres,
+}
+
+impl Driver {
+    pub fn parse(s: &str) -> Option<Driver> {
+        match s.to_lowercase().as_str() {
+            "sqlite" => Some(Driver::Sqlite),
+            "mysql" => Some(Driver::MySql),
+            "postgres" => Some(Driver::Postgres),
+            _ => None,
+        }
+    }
+
+    /// Bind placeholder for the `n`-th (1-based) parameter.
+    pub fn placeholder(&self, n: usize) -> String {
+        match self {
+            Driver::Postgres => format!("${}", n),
+            _ => "?".to_string(),
+        }
+    }
+
+    pub fn backend(&self, naorm: &TokenStream) -> TokenStream {
+        match self {
+            Driver::Sqlite => quote! { #naorm::Backend::Sqlite },
+            Driver::MySql => quote! { #naorm::Backend::MySql },
+            Driver::Postgres => quote! { #naorm::Backend::Postgres },
+        }
+    }
+
+    pub fn placeholder_style(&self, naorm: &TokenStream) -> TokenStream {
+        match self {
+            Driver::Postgres => quote! { #naorm::sql::PlaceholderStyle::Dollar },
+            _ => quote! { #naorm::sql::PlaceholderStyle::Question },
+        }
+    }
+
+    pub fn db(&self) -> TokenStream {
+        match self {
+            Driver::Sqlite => quote! { sqlx::Sqlite },
+            Driver::MySql => quote! { sqlx::MySql },
+            Driver::Postgres => quote! { sqlx::Postgres },
+        }
+    }
+
+    /// Query arguments for lifetime `lt` (only sqlite's borrow, so name them through
+    /// `Database`).
+    pub fn arguments(&self, lt: TokenStream) -> TokenStream {
+        let db = self.db();
+        quote! { <#db as sqlx::Database>::Arguments<#lt> }
+    }
+
+    pub fn row(&self) -> TokenStream {
+        match self {
+            Driver::Sqlite => quote! { sqlx::sqlite::SqliteRow },
+            Driver::MySql => quote! { sqlx::mysql::MySqlRow },
+            Driver::Postgres => quote! { sqlx::postgres::PgRow },
+        }
+    }
+
+    /// Macro expanding its input only when the `naorm` feature of the driver is on.
+    pub fn gate(&self, naorm: &TokenStream) -> TokenStream {
+        match self {
+            Driver::Sqlite => quote! { #naorm::__naorm_sqlite },
+            Driver::MySql => quote! { #naorm::__naorm_mysql },
+            Driver::Postgres => quote! { #naorm::__naorm_postgres },
+        }
+    }
+}
+
+/// Drivers of `drivers = "sqlite, postgres"`, in the order listed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Drivers([Option<Driver>; 3]);
+
+impl Drivers {
+    pub const ALL: Drivers = Drivers([Some(Driver::Sqlite), Some(Driver::MySql), Some(Driver::Postgres)]);
+
+    pub fn parse(s: &str) -> Result<Drivers, String> {
+        let mut list = [None; 3];
+        for (i, name) in s.split(',').map(str::trim).enumerate() {
+            let driver = Driver::parse(name).ok_or_else(|| format!("unknown driver `{}`", name))?;
+            if list.contains(&Some(driver)) || i >= list.len() {
+                return Err(format!("driver `{}` is listed twice", name));
+            }
+            list[i] = Some(driver);
+        }
+        Ok(Drivers(list))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Driver> + '_ {
+        self.0.iter().flatten().copied()
+    }
+}
+
+/// What `#[naorm_cfg(driver = "...")]` selects: one backend, `any` for `sqlx::Any`,
+/// or several drivers (`drivers = "..."`, `driver = "all"`) with methods generic over
+/// `naorm::NaormDialect`. The last two generate every statement once per dialect.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Target {
+    One(Driver),
+    Any,
+    Many(Drivers),
+}
+
+impl Target {
+    pub fn parse(s: &str) -> Option<Target> {
+        match s.to_lowercase().as_str() {
+            "any" => Some(Target::Any),
+            "all" => Some(Target::Many(Drivers::ALL)),
+            _ => Driver::parse(s).map(Target::One),
+        }
+    }
+
+    /// Dialect of the code that is not generated per dialect (field metadata,
+    /// `Model`); sqlite for `any`, the first listed of several drivers.
+    pub fn primary(&self) -> Driver {
+        match self {
+            Target::One(driver) => *driver,
+            Target::Any => Driver::Sqlite,
+            Target::Many(drivers) => drivers.iter().next().unwrap_or(Driver::Sqlite),
+        }
+    }
+
+    pub fn is_any(&self) -> bool {
+        *self == Target::Any
+    }
+
+    /// `driver = "..."` as shown in errors about options only generated for one driver.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Target::One(_) => "one driver",
+            Target::Any => "driver = \"any\"",
+            Target::Many(_) => "several drivers",
+        }
+    }
+
+    /// Database of the `Model` impl.
+    pub fn db(&self) -> TokenStream {
+        match self {
+            Target::Any => quote! { sqlx::Any },
+            _ => self.primary().db(),
+        }
+    }
+
+    pub fn arguments(&self, lt: TokenStream) -> TokenStream {
+        let db = self.db();
+        quote! { <#db as sqlx::Database>::Arguments<#lt> }
+    }
+
+    pub fn row(&self) -> TokenStream {
+        match self {
+            Target::Any => quote! { sqlx::any::AnyRow },
+            _ => self.primary().row(),
+        }
+    }
+
+    /// Type of the SQL constants: `&'static str`, `naorm::sql::DialectSql` for `any`.
+    pub fn sql_type(&self, naorm: &TokenStream) -> TokenStream {
+        match self {
+            Target::One(_) => quote! { &'static str },
+            _ => quote! { #naorm::sql::DialectSql },
+        }
+    }
+
+    /// The statement `sql` builds (as a `&'static str` expression) for each dialect.
+    pub fn sql(&self, naorm: &TokenStream, sql: impl Fn(Driver) -> TokenStream) -> TokenStream {
+        match self {
+            Target::One(driver) => sql(*driver),
+            _ => {
+                let (sqlite, mysql, postgres) = (sql(Driver::Sqlite), sql(Driver::MySql), sql(Driver::Postgres));
+                quote! {
+                    #naorm::sql::DialectSql {
+                        sqlite: #sqlite,
+                        mysql: #mysql,
+                        postgres: #postgres,
+                    }
+                }
+            }
+        }
+    }
+}
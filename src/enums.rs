@@ -0,0 +1,251 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::{abort, emit_error};
+use quote::quote;
+use syn::{Attribute, DeriveInput, LitStr};
+
+use crate::table_create::to_snake_case;
+
+/// Options of `#[naorm_enum(...)]` on the enum.
+struct EnumAttrs {
+    int: bool,
+    native: bool,
+    type_name: Option<String>,
+}
+
+fn parse_enum_attrs(attrs: &[Attribute]) -> EnumAttrs {
+    let mut out = EnumAttrs {
+        int: false,
+        native: false,
+        type_name: None,
+    };
+    for attr in attrs.iter().filter(|a| a.path().is_ident("naorm_enum")) {
+        let res = attr.parse_nested_meta(|meta| {
+            let key = meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+            match key.as_str() {
+                "repr" => {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    out.int = match lit.value().as_str() {
+                        "text" => false,
+                        "int" => true,
+                        _ => return Err(syn::Error::new(lit.span(), "expected `text` or `int`")),
+                    };
+                }
+                "native" => out.native = true,
+                "type_name" => out.type_name = Some(meta.value()?.parse::<LitStr>()?.value()),
+                _ => return Err(meta.error(format!("unknown naorm_enum option `{}`", key))),
+            }
+            Ok(())
+        });
+        if let Err(e) = res {
+            emit_error!(e.span(), "{}", e);
+        }
+    }
+    out
+}
+
+// `#[naorm_enum(rename = "...")]` on a variant
+fn variant_rename(attrs: &[Attribute]) -> Option<String> {
+    let mut rename = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("naorm_enum")) {
+        let res = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown naorm_enum variant option"))
+            }
+        });
+        if let Err(e) = res {
+            emit_error!(e.span(), "{}", e);
+        }
+    }
+    rename
+}
+
+/// Expansion of `#[derive(NaormEnum)]`.
+pub fn derive(input: &DeriveInput, naorm: &TokenStream) -> TokenStream {
+    let ident = &input.ident;
+    let syn::Data::Enum(data) = &input.data else {
+        abort!(input.ident, "NaormEnum can only be derived for enums");
+    };
+    let cfg = parse_enum_attrs(&input.attrs);
+    if cfg.native && cfg.int {
+        emit_error!(input.ident, "`native` enums are stored as text, remove `repr = \"int\"`");
+    }
+
+    // (variant, stored value), and the `i32` value of `repr = "int"` variants
+    let mut variants: Vec<(&syn::Ident, String)> = Vec::new();
+    let mut discriminants: Vec<i32> = Vec::new();
+    let mut next = 0i64;
+    for v in &data.variants {
+        if !matches!(v.fields, syn::Fields::Unit) {
+            emit_error!(v.ident, "NaormEnum variants can not have fields");
+        }
+        let value = if cfg.int {
+            if let Some((_, expr)) = &v.discriminant {
+                match expr {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(i), ..
+                    }) => match i.base10_parse() {
+                        Ok(n) => next = n,
+                        Err(e) => emit_error!(i, "{}", e),
+                    },
+                    _ => emit_error!(expr, "expected an integer literal discriminant"),
+                }
+            }
+            // the column is an INTEGER bound as `i32`
+            match i32::try_from(next) {
+                Ok(n) => discriminants.push(n),
+                Err(_) => emit_error!(v, "discriminant {} of `{}` does not fit an i32", next, v.ident),
+            }
+            let value = next.to_string();
+            next += 1;
+            value
+        } else {
+            variant_rename(&v.attrs).unwrap_or_else(|| to_snake_case(&v.ident.to_string()))
+        };
+        variants.push((&v.ident, value));
+    }
+    if variants.is_empty() {
+        abort!(input.ident, "NaormEnum needs at least one variant");
+    }
+
+    let type_name = cfg
+        .type_name
+        .clone()
+        .unwrap_or_else(|| to_snake_case(&ident.to_string()));
+    let quoted: Vec<String> = variants
+        .iter()
+        .map(|(_, v)| format!("'{}'", v.replace('\'', "''")))
+        .collect();
+    let check_values = if cfg.int {
+        variants.iter().map(|(_, v)| v.clone()).collect::<Vec<_>>().join(", ")
+    } else {
+        quoted.join(", ")
+    };
+    let max_len = variants.iter().map(|(_, v)| v.len()).max().unwrap_or(1);
+    let (sqlite_type, mysql_type, postgres_type) = if cfg.int {
+        ("INTEGER".to_string(), "INT".to_string(), "INTEGER".to_string())
+    } else if cfg.native {
+        (
+            "TEXT".to_string(),
+            format!("ENUM({})", quoted.join(", ")),
+            type_name.clone(),
+        )
+    } else {
+        ("TEXT".to_string(), format!("VARCHAR({})", max_len), "TEXT".to_string())
+    };
+    // postgres has no `CREATE TYPE IF NOT EXISTS`
+    let create_type = if cfg.native {
+        format!(
+            "DO $$ BEGIN CREATE TYPE {} AS ENUM ({}); EXCEPTION WHEN duplicate_object THEN NULL; END $$;\n",
+            type_name,
+            quoted.join(", ")
+        )
+    } else {
+        String::new()
+    };
+
+    let as_str_arms = variants.iter().map(|(v, value)| quote! { #ident::#v => #value });
+    let trait_impl = quote! {
+        impl #naorm::NaormEnum for #ident {
+            const TYPE_NAME: &'static str = #type_name;
+            const CHECK_VALUES: &'static str = #check_values;
+            const SQLITE_TYPE: &'static str = #sqlite_type;
+            const MYSQL_TYPE: &'static str = #mysql_type;
+            const POSTGRES_TYPE: &'static str = #postgres_type;
+            const POSTGRES_CREATE_TYPE_SQL: &'static str = #create_type;
+
+            fn as_sql_str(&self) -> &'static str {
+                match self {
+                    #(#as_str_arms,)*
+                }
+            }
+        }
+    };
+
+    let from_str_arms = variants.iter().map(|(v, value)| quote! { #value => Ok(#ident::#v) });
+    let from_str_impl = quote! {
+        impl ::core::str::FromStr for #ident {
+            type Err = String;
+
+            /// Parses the stored value, as returned by `as_sql_str`.
+            fn from_str(s: &str) -> Result<Self, String> {
+                match s {
+                    #(#from_str_arms,)*
+                    other => Err(format!("unknown {} value `{}`", stringify!(#ident), other)),
+                }
+            }
+        }
+    };
+
+    let drivers = [
+        (quote! { #naorm::__naorm_sqlite }, quote! { sqlx::Sqlite }, false),
+        (quote! { #naorm::__naorm_mysql }, quote! { sqlx::MySql }, false),
+        (quote! { #naorm::__naorm_postgres }, quote! { sqlx::Postgres }, cfg.native),
+    ];
+    let driver_impls = drivers.iter().map(|(gate, db, native)| {
+        let (repr, decode_arms) = if cfg.int {
+            let arms = variants.iter().zip(&discriminants).map(|((v, _), n)| quote! { #n => Ok(#ident::#v) });
+            (quote! { i32 }, quote! { #(#arms,)* })
+        } else {
+            let arms = variants.iter().map(|(v, value)| quote! { #value => Ok(#ident::#v) });
+            (quote! { &str }, quote! { #(#arms,)* })
+        };
+        let encode_value = if cfg.int {
+            let arms = variants.iter().zip(&discriminants).map(|((v, _), n)| quote! { #ident::#v => #n });
+            quote! { &match self { #(#arms,)* } }
+        } else {
+            quote! { &#naorm::NaormEnum::as_sql_str(self) }
+        };
+        let type_impl = if *native {
+            quote! {
+                fn type_info() -> <#db as sqlx::Database>::TypeInfo {
+                    sqlx::postgres::PgTypeInfo::with_name(#type_name)
+                }
+            }
+        } else {
+            quote! {
+                fn type_info() -> <#db as sqlx::Database>::TypeInfo {
+                    <#repr as sqlx::Type<#db>>::type_info()
+                }
+                fn compatible(ty: &<#db as sqlx::Database>::TypeInfo) -> bool {
+                    <#repr as sqlx::Type<#db>>::compatible(ty)
+                }
+            }
+        };
+        quote! {
+            #gate! {
+                impl sqlx::Type<#db> for #ident {
+                    #type_impl
+                }
+
+                impl<'q> sqlx::Encode<'q, #db> for #ident {
+                    fn encode_by_ref(
+                        &self,
+                        buf: &mut <#db as sqlx::Database>::ArgumentBuffer<'q>,
+                    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                        <#repr as sqlx::Encode<'q, #db>>::encode_by_ref(#encode_value, buf)
+                    }
+                }
+
+                impl<'r> sqlx::Decode<'r, #db> for #ident {
+                    fn decode(
+                        value: <#db as sqlx::Database>::ValueRef<'r>,
+                    ) -> Result<Self, sqlx::error::BoxDynError> {
+                        match <#repr as sqlx::Decode<'r, #db>>::decode(value)? {
+                            #decode_arms
+                            other => Err(format!("unknown {} value `{}`", stringify!(#ident), other).into()),
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    quote! {
+        #trait_impl
+        #from_str_impl
+        #(#driver_impls)*
+    }
+}
@@ -0,0 +1,239 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::driver::Driver;
+use crate::table_create;
+
+/// Generated `tsvector` column of postgres tables with `fulltext` fields.
+const TSVECTOR_COLUMN: &str = "naorm_tsv";
+
+/// Columns marked `#[naorm_cfg(fulltext)]` and the table they belong to.
+pub struct FullText<'a> {
+    pub table: &'a str,
+    pub columns: Vec<String>,
+    /// Every selected column, in `SELECT_SQL` order.
+    pub select: &'a [String],
+}
+
+impl FullText<'_> {
+    fn fts_table(&self) -> String {
+        format!("{}_fts", self.table)
+    }
+
+    fn qualified_select(&self) -> String {
+        self.select
+            .iter()
+            .map(|c| format!("{}.{}", self.table, c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn index_name(&self) -> String {
+        format!("{}_fulltext", self.table)
+    }
+
+    // postgres: all fulltext columns as one document
+    fn pg_document(&self) -> String {
+        self.columns
+            .iter()
+            .map(|c| format!("coalesce({}, '')", c))
+            .collect::<Vec<_>>()
+            .join(" || ' ' || ")
+    }
+
+    // `prefix.a, prefix.b`
+    fn columns_of(&self, prefix: &str) -> String {
+        self.columns
+            .iter()
+            .map(|c| format!("{}.{}", prefix, c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Statements run by `create_table` after the table and its indexes.
+    pub fn statements(&self, driver: Driver) -> Vec<String> {
+        match driver {
+            Driver::Sqlite => {
+                let (t, fts, cols) = (self.table, self.fts_table(), self.columns.join(", "));
+                // external content table, kept in sync by triggers
+                let insert = format!(
+                    "INSERT INTO {} (rowid, {}) VALUES (new.rowid, {})",
+                    fts,
+                    cols,
+                    self.columns_of("new")
+                );
+                let delete = format!(
+                    "INSERT INTO {} ({}, rowid, {}) VALUES ('delete', old.rowid, {})",
+                    fts,
+                    fts,
+                    cols,
+                    self.columns_of("old")
+                );
+                vec![
+                    format!(
+                        "CREATE VIRTUAL TABLE IF NOT EXISTS {} USING fts5({}, content='{}')",
+                        fts, cols, t
+                    ),
+                    format!(
+                        "CREATE TRIGGER IF NOT EXISTS {}_ai AFTER INSERT ON {} BEGIN {}; END",
+                        fts, t, insert
+                    ),
+                    format!(
+                        "CREATE TRIGGER IF NOT EXISTS {}_ad AFTER DELETE ON {} BEGIN {}; END",
+                        fts, t, delete
+                    ),
+                    format!(
+                        "CREATE TRIGGER IF NOT EXISTS {}_au AFTER UPDATE ON {} BEGIN {}; {}; END",
+                        fts, t, delete, insert
+                    ),
+                ]
+            }
+            Driver::MySql => {
+                let create = format!(
+                    "CREATE FULLTEXT INDEX {} ON {} ({})",
+                    self.index_name(),
+                    self.table,
+                    self.columns.join(", ")
+                );
+                table_create::mysql_create_index_if_missing(self.table, &self.index_name(), &create)
+            }
+            Driver::Postgres => vec![
+                format!(
+                    "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} tsvector \
+                     GENERATED ALWAYS AS (to_tsvector('english', {})) STORED",
+                    self.table,
+                    TSVECTOR_COLUMN,
+                    self.pg_document()
+                ),
+                format!(
+                    "CREATE INDEX IF NOT EXISTS {} ON {} USING GIN ({})",
+                    self.index_name(),
+                    self.table,
+                    TSVECTOR_COLUMN
+                ),
+            ],
+        }
+    }
+
+    /// `SEARCH_SQL`, ranked best first, with the number of times the term is bound.
+    fn search_sql(&self, driver: Driver, hits: bool) -> (String, usize) {
+        match driver {
+            Driver::Sqlite => {
+                let fts = self.fts_table();
+                // bm25 `rank` is lower for better matches
+                let extra = if hits {
+                    format!(
+                        ", -{fts}.rank AS naorm_rank, snippet({fts}, -1, '<b>', '</b>', '...', 16) AS naorm_snippet, \
+                         highlight({fts}, 0, '<b>', '</b>') AS naorm_highlight"
+                    )
+                } else {
+                    String::new()
+                };
+                (
+                    format!(
+                        "SELECT {}{} FROM {} JOIN {} ON {}.rowid = {}.rowid WHERE {} MATCH ? ORDER BY {}.rank",
+                        self.qualified_select(),
+                        extra,
+                        self.table,
+                        fts,
+                        fts,
+                        self.table,
+                        fts,
+                        fts
+                    ),
+                    1,
+                )
+            }
+            Driver::MySql => {
+                let against = format!(
+                    "MATCH ({}) AGAINST (? IN NATURAL LANGUAGE MODE)",
+                    self.columns_of(self.table)
+                );
+                if hits {
+                    // no excerpt functions in mysql
+                    (
+                        format!(
+                            "SELECT {}, {} AS naorm_rank, CAST(NULL AS CHAR) AS naorm_snippet, \
+                             CAST(NULL AS CHAR) AS naorm_highlight FROM {} WHERE {} ORDER BY naorm_rank DESC",
+                            self.qualified_select(),
+                            against,
+                            self.table,
+                            against
+                        ),
+                        2,
+                    )
+                } else {
+                    (
+                        format!(
+                            "SELECT {} FROM {} WHERE {} ORDER BY {} DESC",
+                            self.qualified_select(),
+                            self.table,
+                            against,
+                            against
+                        ),
+                        2,
+                    )
+                }
+            }
+            Driver::Postgres => {
+                let (t, tsv) = (self.table, TSVECTOR_COLUMN);
+                let query = "websearch_to_tsquery('english', $1)";
+                let rank = format!("ts_rank({}.{}, {})", t, tsv, query);
+                let extra = if hits {
+                    format!(
+                        ", {rank}::float8 AS naorm_rank, \
+                         ts_headline('english', {doc}, {query}, 'StartSel=<b>, StopSel=</b>') AS naorm_snippet, \
+                         ts_headline('english', coalesce({first}, ''), {query}, 'StartSel=<b>, StopSel=</b>, HighlightAll=true') \
+                         AS naorm_highlight",
+                        doc = self.pg_document(),
+                        first = self.columns[0],
+                    )
+                } else {
+                    String::new()
+                };
+                (
+                    format!(
+                        "SELECT {}{} FROM {} WHERE {}.{} @@ {} ORDER BY {} DESC",
+                        self.qualified_select(),
+                        extra,
+                        t,
+                        t,
+                        tsv,
+                        query,
+                        rank
+                    ),
+                    1,
+                )
+            }
+        }
+    }
+
+    /// `SEARCH_SQL`, `search_query(term)` and `search_hits_query(term)`.
+    pub fn methods(&self, driver: Driver, naorm: &TokenStream) -> TokenStream {
+        let db = driver.db();
+        let args = driver.arguments(quote! { 'q });
+        let row = driver.row();
+        let (search_sql, binds) = self.search_sql(driver, false);
+        let (hits_sql, _) = self.search_sql(driver, true);
+        let bind = (0..binds).map(|_| quote! { .bind(term) });
+        let hits_bind = (0..binds).map(|_| quote! { .bind(term) });
+        quote! {
+            pub const SEARCH_SQL: &'static str = #search_sql;
+
+            /// Rows matching the full-text `term`, best match first.
+            pub fn search_query<'q>(term: &'q str) -> sqlx::query::QueryAs<'q, #db, Self, #args>
+            where
+                Self: for<'r> sqlx::FromRow<'r, #row>,
+            {
+                sqlx::query_as::<#db, Self>(Self::SEARCH_SQL) #(#bind)*
+            }
+
+            /// Like `search_query`, with the rank and highlighted excerpts of each match.
+            pub fn search_hits_query<'q>(
+                term: &'q str,
+            ) -> sqlx::query::QueryAs<'q, #db, #naorm::search::Hit<Self>, #args> {
+                sqlx::query_as::<#db, #naorm::search::Hit<Self>>(#hits_sql) #(#hits_bind)*
+            }
+        }
+    }
+}
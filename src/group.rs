@@ -0,0 +1,236 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::{abort, emit_error};
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Fields};
+
+use crate::attrs::FieldAttrs;
+use crate::driver::{Driver, Target};
+use crate::table_create::{self, Column};
+use crate::types::FieldType;
+
+/// Options of a field that a `flatten` field, or a field of a `NaormColumns` type,
+/// can not take.
+fn unsupported_options(cfg: &FieldAttrs, in_group: bool) -> Vec<&'static str> {
+    let options = [
+        (cfg.primary_key, "primary_key"),
+        (cfg.auto_increment, "auto_increment"),
+        (cfg.index, "index"),
+        (cfg.unique, "unique"),
+        (cfg.fulltext, "fulltext"),
+        (cfg.is_enum, "enum"),
+        (cfg.belongs_to.is_some(), "belongs_to"),
+        (cfg.flatten, "flatten"),
+        (cfg.renamed_from.is_some() && in_group, "renamed_from"),
+    ];
+    let field_options = [
+        (cfg.json, "json"),
+        (cfg.transparent, "transparent"),
+        (cfg.bind_with.is_some() || cfg.decode_with.is_some(), "bind_with/decode_with"),
+        (cfg.default.is_some(), "default"),
+        (!cfg.sql_type.per_driver.is_empty() || cfg.sql_type.all.is_some(), "sql_type"),
+        (cfg.max_len.is_some() || cfg.min.is_some() || cfg.max.is_some(), "validation rules"),
+        (cfg.regex.is_some() || cfg.not_empty || cfg.validate_with.is_some(), "validation rules"),
+    ];
+    let mut out: Vec<&'static str> = options.iter().filter(|(set, _)| *set).map(|(_, o)| *o).collect();
+    if !in_group {
+        out.extend(field_options.iter().filter(|(set, _)| *set).map(|(_, o)| *o));
+    }
+    out.dedup();
+    out
+}
+
+/// Checks a field of a `NaormReg` struct: `prefix` needs `flatten`, which takes a plain
+/// `NaormColumns` type.
+pub fn check_field(ident: &syn::Ident, cfg: &FieldAttrs, ty: &FieldType) {
+    if !cfg.flatten {
+        if cfg.prefix.is_some() {
+            emit_error!(ident, "`prefix` of field `{}` needs `flatten`", ident);
+        }
+        return;
+    }
+    if ty.is_option() {
+        emit_error!(ident, "`flatten` field `{}` can not be an `Option`", ident);
+    }
+    let mut flatten = cfg.clone();
+    flatten.flatten = false;
+    for option in unsupported_options(&flatten, false) {
+        emit_error!(ident, "`flatten` field `{}` can not take `{}`", ident, option);
+    }
+}
+
+/// Prefixed names of the `flatten` column `col`, a `[&'static str; N]` expression.
+pub fn names(naorm: &TokenStream, col: &Column) -> TokenStream {
+    let ty = &col.ty.inner;
+    let prefix = col.attrs.prefix.clone().unwrap_or_default();
+    quote! {
+        {
+            const COLUMNS: &[#naorm::group::GroupColumn] = <#ty as #naorm::ColumnGroup>::COLUMNS;
+            const BYTES: [u8; #naorm::group::prefixed_names_len(#prefix, COLUMNS)] =
+                #naorm::group::prefixed_names(#prefix, COLUMNS);
+            const NAMES: [&str; COLUMNS.len()] = #naorm::group::split_names(&BYTES, #prefix, COLUMNS);
+            NAMES
+        }
+    }
+}
+
+/// `NAORM_FIELDS` entries of the `flatten` column `col`, with the column types of
+/// `driver`.
+pub fn fields(naorm: &TokenStream, driver: Driver, col: &Column) -> TokenStream {
+    let ty = &col.ty.inner;
+    let names = names(naorm, col);
+    let backend = driver.backend(naorm);
+    quote! {
+        {
+            const COLUMNS: &[#naorm::group::GroupColumn] = <#ty as #naorm::ColumnGroup>::COLUMNS;
+            const NAMES: [&str; COLUMNS.len()] = #names;
+            const FIELDS: [#naorm::FieldInfo; COLUMNS.len()] =
+                #naorm::group::fields(&NAMES, COLUMNS, Some(#backend));
+            FIELDS
+        }
+    }
+}
+
+/// `parts` (`&[T]` expressions) joined into a `&'static [T]` at compile time.
+pub fn concat(naorm: &TokenStream, ty: TokenStream, parts: &[TokenStream], fill: TokenStream) -> TokenStream {
+    quote! {
+        {
+            const PARTS: &[&[#ty]] = &[#(#parts),*];
+            const ALL: [#ty; #naorm::group::slices_len(PARTS)] = #naorm::group::concat_slices(PARTS, #fill);
+            &ALL
+        }
+    }
+}
+
+/// `#[derive(NaormColumns)]`: `naorm::ColumnGroup`, and `naorm::group::GroupCodec` for
+/// the drivers enabled as naorm features.
+pub fn derive(input: &DeriveInput, naorm: &TokenStream) -> TokenStream {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: Fields::Named(named),
+            ..
+        }) => named,
+        _ => abort!(ident, "NaormColumns can only be derived for structs with named fields"),
+    };
+    if !input.generics.params.is_empty() {
+        emit_error!(input.generics, "NaormColumns can not be derived for generic structs");
+    }
+    let mut columns: Vec<Column> = Vec::new();
+    for field in &fields.named {
+        let field_ident = field.ident.as_ref().unwrap();
+        let mut cfg = FieldAttrs::parse(&field.attrs);
+        cfg.json |= crate::attrs::has_sqlx_json(&field.attrs);
+        let mut ty = FieldType::analyze(&field.ty);
+        if let Some(wrapped) = &cfg.wrapped {
+            ty.describe_as(wrapped);
+        }
+        crate::check_conversions(field_ident, &cfg, &ty);
+        for option in unsupported_options(&cfg, true) {
+            emit_error!(field_ident, "`{}` is not supported in NaormColumns fields", option);
+        }
+        columns.push(Column {
+            name: field_ident.to_string(),
+            ty_name: ty.name.clone(),
+            is_option: ty.is_option(),
+            indexed: false,
+            enum_ty: None,
+            attrs: cfg,
+            ty,
+        });
+    }
+
+    let group_columns = columns.iter().map(|col| {
+        let name = &col.name;
+        let field_type = &col.ty.full;
+        let nullable = col.is_option;
+        let default_value = crate::listed_default(&col.attrs, &col.ty);
+        let default_sql = match table_create::default_sql(col) {
+            Some(d) => quote! { Some(#d) },
+            None => quote! { None },
+        };
+        // the name is written with the prefix marker wherever the DDL uses it
+        let marked = Column {
+            name: format!("{}{}", table_create::PREFIX_MARKER, col.name),
+            ty_name: col.ty_name.clone(),
+            is_option: col.is_option,
+            indexed: false,
+            enum_ty: None,
+            attrs: col.attrs.clone(),
+            ty: col.ty.clone(),
+        };
+        let sql_type = Target::Any.sql(naorm, |d| {
+            let ty = table_create::sql_type(d, col);
+            quote! { #ty }
+        });
+        let definition = Target::Any.sql(naorm, |d| {
+            let def = table_create::column_def(d, &marked);
+            quote! { #def }
+        });
+        quote! {
+            #naorm::group::GroupColumn {
+                name: #name,
+                field_type: #field_type,
+                nullable: #nullable,
+                default_value: #default_value,
+                default_sql: #default_sql,
+                sql_type: #sql_type,
+                definition: #definition,
+            }
+        }
+    });
+    let validate_tokens: Vec<TokenStream> = columns
+        .iter()
+        .map(|col| crate::validate::column_checks(naorm, col))
+        .filter(|checks| !checks.is_empty())
+        .collect();
+    let validate_columns = (!validate_tokens.is_empty()).then(|| {
+        quote! {
+            fn validate_columns(&self, errors: &mut #naorm::ValidationErrors) {
+                let mut errors = errors;
+                #(#validate_tokens)*
+            }
+        }
+    });
+
+    let codecs = [Driver::Sqlite, Driver::MySql, Driver::Postgres].map(|driver| {
+        let (gate, db, row) = (driver.gate(naorm), driver.db(), driver.row());
+        let args = driver.arguments(quote! { 'q });
+        let binds = columns.iter().map(|col| {
+            let field = format_ident!("{}", col.name);
+            let marker = format_ident!("as_{}", table_create::to_snake_case(&col.ty_name));
+            crate::bind_field(&field, &marker, col)
+        });
+        let decode = columns.iter().map(|col| {
+            let field = format_ident!("{}", col.name);
+            crate::decode_field(naorm, &db, col, &field)
+        });
+        quote! {
+            #gate! {
+                impl #naorm::group::GroupCodec<#db> for #ident {
+                    fn bind_columns<'q>(
+                        &'q self,
+                        query: sqlx::query::Query<'q, #db, #args>,
+                    ) -> sqlx::query::Query<'q, #db, #args> {
+                        query #(#binds)*
+                    }
+
+                    fn decode_columns(row: &#row, prefix: &str) -> Result<Self, sqlx::Error> {
+                        Ok(Self {
+                            #(#decode)*
+                        })
+                    }
+                }
+            }
+        }
+    });
+
+    quote! {
+        impl #naorm::ColumnGroup for #ident {
+            const COLUMNS: &'static [#naorm::group::GroupColumn] = &[#(#group_columns),*];
+
+            #validate_columns
+        }
+
+        #(#codecs)*
+    }
+}
@@ -0,0 +1,40 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::driver::Driver;
+
+/// `{field}_path_query(path, value)`: rows whose `json` column holds `value` (compared
+/// as text) at the dotted `path`.
+pub fn path_query(driver: Driver, naorm: &TokenStream, select_sql: &str, column: &str) -> TokenStream {
+    let db = driver.db();
+    let args = driver.arguments(quote! { 'q });
+    let row = driver.row();
+    let fn_name = format_ident!("{}_path_query", column);
+    let (sql, path) = match driver {
+        Driver::Sqlite => (
+            format!("{} WHERE CAST(json_extract({}, ?) AS TEXT) = ?", select_sql, column),
+            quote! { #naorm::json::json_path(path) },
+        ),
+        Driver::MySql => (
+            format!("{} WHERE JSON_UNQUOTE(JSON_EXTRACT({}, ?)) = ?", select_sql, column),
+            quote! { #naorm::json::json_path(path) },
+        ),
+        Driver::Postgres => (
+            format!("{} WHERE {} #>> $1 = $2", select_sql, column),
+            quote! { #naorm::json::pg_path(path) },
+        ),
+    };
+    let doc = format!(
+        " Rows whose `{}` holds `value` at `path` (`theme.color`, `tags[0]`).",
+        column
+    );
+    quote! {
+        #[doc = #doc]
+        pub fn #fn_name<'q>(path: &str, value: &'q str) -> sqlx::query::QueryAs<'q, #db, Self, #args>
+        where
+            Self: for<'r> sqlx::FromRow<'r, #row>,
+        {
+            sqlx::query_as::<#db, Self>(#sql).bind(#path).bind(value)
+        }
+    }
+}
@@ -1,67 +1,312 @@
+mod aggregate;
+mod attrs;
+mod defaults;
+mod driver;
+mod enums;
+mod fulltext;
+mod group;
+mod json;
+mod projection;
+mod relations;
+mod schema;
+mod stream;
 mod table_create;
+mod types;
+mod validate;
 use proc_macro::TokenStream;
-use proc_macro_error::{abort, emit_error};
-use quote::{format_ident, quote, ToTokens};
-use syn::parse2;
+use proc_macro_error::{abort, emit_error, proc_macro_error};
+use quote::{format_ident, quote};
 
-use syn::MetaNameValue;
-use syn::{parse_macro_input, DeriveInput, Expr, Fields, LitStr, Meta};
+use attrs::{FieldAttrs, StructAttrs};
+use driver::{Driver, Target};
+use relations::BelongsTo;
+use syn::{parse_macro_input, parse_quote, DeriveInput, Fields, LitStr};
+use table_create::Column;
+
+/// Path of the `naorm` runtime crate as seen from the crate using the derive.
+fn naorm_path() -> proc_macro2::TokenStream {
+    match proc_macro_crate::crate_name("naorm") {
+        Ok(proc_macro_crate::FoundCrate::Name(name)) => {
+            let ident = format_ident!("{}", name);
+            quote! { ::#ident }
+        }
+        // `naorm` itself declares `extern crate self as naorm`, which also covers its tests
+        _ => quote! { ::naorm },
+    }
+}
+
+pub(crate) fn bind_token(ident: &syn::Ident, field_type: &syn::Ident, is_option: bool) -> proc_macro2::TokenStream {
+    bind_value(&quote! { self.#ident }, field_type, is_option)
+}
+
+/// `.bind(...)` for an arbitrary place expression such as `self.id` or `parent.id`.
+pub(crate) fn bind_value(
+    value: &proc_macro2::TokenStream,
+    field_type: &syn::Ident,
+    is_option: bool,
+) -> proc_macro2::TokenStream {
+    match (is_option, *field_type == "as_string") {
+        (true, true) => quote! { .bind(#value.as_deref()) },
+        (true, false) => quote! { .bind(#value.as_ref()) },
+        (false, true) => quote! { .bind(#value.as_str()) },
+        (false, false) => quote! { .bind(&#value) },
+    }
+}
+
+/// `.bind(...)` of a column of the struct: `bind_with` functions and the field of
+/// `transparent` newtypes are bound instead of the value, `json` values go through
+/// `sqlx::types::Json`, byte arrays are bound as slices, borrowed text and slices
+/// through a deref and nested `Option`s are flattened.
+fn bind_field(ident: &syn::Ident, field_type: &syn::Ident, col: &Column) -> proc_macro2::TokenStream {
+    let depth = col.ty.option_depth;
+    if let Some(bind_with) = &col.attrs.bind_with {
+        return quote! { .bind(#bind_with(&self.#ident)) };
+    }
+    if col.attrs.transparent {
+        if depth == 0 {
+            return quote! { .bind(&self.#ident.0) };
+        }
+        let flatten = (1..depth).map(|_| quote! { .and_then(|v| v.as_ref()) });
+        return quote! { .bind(self.#ident.as_ref() #(#flatten)* .map(|v| &v.0)) };
+    }
+    if col.attrs.json {
+        return if depth > 0 {
+            quote! { .bind(self.#ident.as_ref().map(sqlx::types::Json)) }
+        } else {
+            quote! { .bind(sqlx::types::Json(&self.#ident)) }
+        };
+    }
+    if col.ty.is_borrowed() && depth <= 1 {
+        return if depth == 1 {
+            quote! { .bind(self.#ident.as_deref()) }
+        } else {
+            quote! { .bind(&*self.#ident) }
+        };
+    }
+    if !col.ty.via_vec && depth <= 1 {
+        return bind_token(ident, field_type, depth == 1);
+    }
+    if depth == 0 {
+        return quote! { .bind(&self.#ident[..]) };
+    }
+    // `Option<Option<T>>` -> `Option<&T>`
+    let flatten = (1..depth).map(|_| quote! { .and_then(|v| v.as_ref()) });
+    let finish = if col.ty.via_vec {
+        quote! { .map(|v| &v[..]) }
+    } else if *field_type == "as_string" {
+        quote! { .map(String::as_str) }
+    } else {
+        quote! {}
+    };
+    quote! { .bind(self.#ident.as_ref() #(#flatten)* #finish) }
+}
+
+/// A bind of a generated query: a `.bind(...)` call, or the columns of a `flatten`
+/// field bound through `naorm::group::GroupCodec`.
+enum Bind {
+    Call(proc_macro2::TokenStream),
+    Group(syn::Ident),
+}
+
+/// `query` with `binds` applied in order.
+fn chain_binds(naorm: &proc_macro2::TokenStream, query: proc_macro2::TokenStream, binds: &[Bind]) -> proc_macro2::TokenStream {
+    binds.iter().fold(query, |query, bind| match bind {
+        Bind::Call(call) => quote! { #query #call },
+        Bind::Group(ident) => quote! { #naorm::group::GroupCodec::bind_columns(&self.#ident, #query) },
+    })
+}
+
+/// `transparent`, `bind_with` and `decode_with` replace the usual binds and decoding,
+/// and exclude each other and `json`.
+fn check_conversions(ident: &syn::Ident, cfg: &FieldAttrs, ty: &types::FieldType) {
+    let custom = cfg.bind_with.is_some() || cfg.decode_with.is_some();
+    if cfg.json && (cfg.transparent || custom) {
+        emit_error!(ident, "`json` fields can not be `transparent` or use `bind_with`/`decode_with`");
+    }
+    if !cfg.transparent {
+        return;
+    }
+    if custom {
+        emit_error!(ident, "`transparent` fields can not use `bind_with`/`decode_with`");
+    }
+    if types::ctor_path(&ty.inner).is_none() {
+        emit_error!(ident, "`transparent` needs a tuple struct type, found `{}`", ty.full);
+    }
+    if cfg.wrapped.is_none() && cfg.sql_type.all.is_none() && cfg.sql_type.per_driver.is_empty() {
+        emit_error!(
+            ident,
+            "`transparent` field `{}` needs the wrapped type, `transparent = \"i64\"`, or a `sql_type`",
+            ident
+        );
+    }
+}
+
+/// `ident: value,` decoding a column of the struct from `row`, reading the column
+/// `{prefix}{name}`; `flatten` fields decode their group with the prefix extended.
+fn decode_field(
+    naorm: &proc_macro2::TokenStream,
+    db: &proc_macro2::TokenStream,
+    col: &Column,
+    ident: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let name = col.name.as_str();
+    let is_option = col.is_option;
+    if col.attrs.flatten {
+        let ty = &col.ty.inner;
+        let group_prefix = col.attrs.prefix.clone().unwrap_or_default();
+        return quote! {
+            #ident: <#ty as #naorm::group::GroupCodec<#db>>::decode_columns(
+                row,
+                &format!("{}{}", prefix, #group_prefix),
+            )?,
+        };
+    }
+    let get = quote! { sqlx::Row::try_get(row, format!("{}{}", prefix, #name).as_str()) };
+    let get_as = |ty| quote! { sqlx::Row::try_get::<#ty, _>(row, format!("{}{}", prefix, #name).as_str()) };
+    if let Some(decode_with) = &col.attrs.decode_with {
+        return quote! {
+            #ident: #decode_with(#get?).map_err(|e| sqlx::Error::Decode(e.into()))?,
+        };
+    }
+    if col.attrs.transparent
+        && let Some(ctor) = types::ctor_path(&col.ty.inner)
+    {
+        return if is_option {
+            quote! { #ident: Option::map(#get?, #ctor), }
+        } else {
+            quote! { #ident: #ctor(#get?), }
+        };
+    }
+    if let Some(borrowed) = &col.ty.cow
+        && !col.attrs.json
+    {
+        // rows outlive no borrow, `Cow`s are decoded owned
+        let owned = quote! { <#borrowed as ::std::borrow::ToOwned>::Owned };
+        return if is_option {
+            let get = get_as(quote! { Option<#owned> });
+            quote! { #ident: #get?.map(::std::borrow::Cow::Owned), }
+        } else {
+            let get = get_as(owned);
+            quote! { #ident: ::std::borrow::Cow::Owned(#get?), }
+        };
+    }
+    match (col.attrs.json, col.ty.via_vec, is_option) {
+        (true, _, false) => {
+            let get = get_as(quote! { sqlx::types::Json<_> });
+            quote! { #ident: #get?.0, }
+        }
+        (true, _, true) => {
+            let get = get_as(quote! { Option<sqlx::types::Json<_>> });
+            quote! { #ident: #get?.map(|j| j.0), }
+        }
+        // `[u8; N]` and `Bytes` decode through `Vec<u8>`
+        (false, true, false) if col.ty.name != "Array" => {
+            let get = get_as(quote! { Vec<u8> });
+            quote! { #ident: #get?.into(), }
+        }
+        (false, true, true) if col.ty.name != "Array" => {
+            let get = get_as(quote! { Option<Vec<u8>> });
+            quote! { #ident: #get?.map(Into::into), }
+        }
+        (false, true, false) => {
+            let get = get_as(quote! { Vec<u8> });
+            quote! {
+                #ident: #get?.as_slice().try_into().map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            }
+        }
+        (false, true, true) => {
+            let get = get_as(quote! { Option<Vec<u8>> });
+            quote! {
+                #ident: #get?
+                    .map(|v| v.as_slice().try_into())
+                    .transpose()
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            }
+        }
+        _ => quote! { #ident: #get?, },
+    }
+}
+
+/// Default of a field as listed in `NAORM_FIELDS`: the `default = ...` value, or the
+/// one inferred from the type.
+fn listed_default(cfg: &FieldAttrs, field_ty: &types::FieldType) -> String {
+    if let Some(default) = &cfg.default {
+        return default.text();
+    }
+    let inferred = match field_ty.name.as_str() {
+        "String" => "",
+        "u64" | "u32" | "u8" | "i64" | "i32" | "usize" | "isize" | "f64" | "f32" => "0",
+        "float" | "float32" | "float64" => "0.0",
+        "bool" => "false",
+        _ => field_ty.library_type().map(|t| t.default_value()).unwrap_or_default(),
+    };
+    inferred.to_string()
+}
+
+#[proc_macro_derive(NaormEnum, attributes(naorm_enum))]
+#[proc_macro_error]
+pub fn naorm_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    enums::derive(&input, &naorm_path()).into()
+}
+
+#[proc_macro_derive(NaormColumns, attributes(naorm_cfg))]
+#[proc_macro_error]
+pub fn naorm_columns(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    group::derive(&input, &naorm_path()).into()
+}
+
+#[proc_macro_derive(NaormProjection, attributes(naorm_cfg))]
+#[proc_macro_error]
+pub fn naorm_projection(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    projection::derive(&input, &naorm_path()).into()
+}
 
 #[proc_macro_derive(NaormReg, attributes(naorm_cfg))]
+#[proc_macro_error]
 pub fn naorm(attr: TokenStream) -> TokenStream {
     let input = parse_macro_input!(attr as DeriveInput);
-    let mut table_name: Option<String> = None;
-    let mut db_name: Option<String> = None;
-    let mut table_type: Option<String> = None;
-    let mut driver: Option<String> = None;
-
-    input.attrs.iter().for_each(|attr| {
-        if attr.meta.path().is_ident("naorm_cfg") {
-            match &attr.meta {
-                Meta::List(data) => {
-                    // println!("Found naorm_cfg attribute: {:?}", data);
-                    // data.tokins 是 parse2:TokenStream ， 强行转换成 MetaNameValue 来解析 key=value 形式的配置
-                    if let Ok(nv) = parse2::<MetaNameValue>(data.tokens.clone()) {
-                        let ident_str = match nv.path.segments.first() {
-                            Some(seg) => seg.ident.to_string(),
-                            None => "".to_string(),
-                        };
-                        // println!("{:?}{:?}", ident_str, &nv.value.lit);
-                        match &nv.value {
-                            Expr::Lit(expr_lit) => {
-                                if let syn::Lit::Str(lit_str) = &expr_lit.lit {
-                                    let val_str = lit_str.value();
-                                    match ident_str.as_str() {
-                                        "table_name" => table_name = Some(val_str),
-                                        "db_name" => db_name = Some(val_str),
-                                        "table_type" => table_type = Some(val_str),
-                                        "driver" => driver = Some(val_str),
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else {
-                        println!(
-                            "Failed ========== to parse naorm_cfg tokens: {:?}",
-                            data.tokens
-                        );
-                    }
-                }
+    let naorm = naorm_path();
+    let struct_cfg = StructAttrs::parse(&input.attrs);
+    let table_name = struct_cfg.table_name.clone();
+    let db_name = struct_cfg.db_name.clone();
+    let table_type = struct_cfg.table_type.clone();
 
-                _ => (),
-            };
+    // driver
+    let target = match (&struct_cfg.driver, &struct_cfg.drivers) {
+        (Some(d), Some(_)) => {
+            emit_error!(d, "use either `driver` or `drivers`");
+            Target::One(Driver::Sqlite)
         }
-    });
+        (Some(d), None) => Target::parse(&d.value()).unwrap_or_else(|| {
+            emit_error!(d, format!("Unsupported driver specified: {}", d.value()));
+            Target::One(Driver::Sqlite)
+        }),
+        (None, Some(list)) => match driver::Drivers::parse(&list.value()) {
+            Ok(drivers) => Target::Many(drivers),
+            Err(e) => {
+                emit_error!(list, e);
+                Target::One(Driver::Sqlite)
+            }
+        },
+        // default to sqlite
+        (None, None) => Target::One(Driver::Sqlite),
+    };
+    let driver = target.primary();
+    let sql_lit = |sql: String| {
+        let lit = LitStr::new(&sql, proc_macro2::Span::call_site());
+        quote! { #lit }
+    };
 
     //get fields from struct, only support named fields for now (struct with named fields)
     let struct_ident = &input.ident;
     let table_lit =
         table_create::to_snake_case(&table_name.unwrap_or_else(|| struct_ident.to_string()));
-    let db_lit = db_name.unwrap_or_else(|| "".to_string());
+    let db_lit = db_name.unwrap_or_default();
 
-    let table_type_lit = table_type.unwrap_or_else(|| "".to_string());
+    let table_type_lit = table_type.unwrap_or_default();
 
     let mut field_tokens: Vec<proc_macro2::TokenStream> = Vec::new();
     let fields = match &input.data {
@@ -82,98 +327,53 @@ pub fn naorm(attr: TokenStream) -> TokenStream {
 
     // name,name_type, value_type, option, default_value
     let mut fields_type: Vec<(String, syn::Ident, syn::Ident, bool, String)> = Vec::new();
+    let mut columns: Vec<Column> = Vec::new();
     let mut pk: String = String::new();
     let mut pk_auto_inc: bool = false;
-    let mut pk_type: Option<String> = None;
+    let mut pk_field: Option<&syn::Field> = None;
     let mut insert_fields: Vec<String> = Vec::new();
     // (name, ident, field_type_ident, is_option)
     let mut insert_field_meta: Vec<(String, syn::Ident, syn::Ident, bool)> = Vec::new();
     for field in &fields.named {
         let ident = field.ident.as_ref().unwrap();
-        let mut ty_name = "_".to_string();
-        let mut is_option = false;
-        let mut is_auto_increment = false;
-        let mut is_primary_key = false;
-        let mut default_value: Option<String> = None;
-        // println!("Named Field: {:?}, Type: {:?}", ident, field);
-
-        match &field.ty {
-            syn::Type::Path(tp) => {
-                if let Some(seg) = tp.path.segments.last() {
-                    let seg_ident = seg.ident.to_string();
-                    if seg_ident == "Option" {
-                        // try extract inner type
-                        if let syn::PathArguments::AngleBracketed(ab) = &seg.arguments {
-                            if let Some(syn::GenericArgument::Type(syn::Type::Path(inner_tp))) =
-                                ab.args.first()
-                            {
-                                if let Some(inner_seg) = inner_tp.path.segments.last() {
-                                    ty_name = inner_seg.ident.to_string();
-                                }
-                            }
-                        }
-                        is_option = true;
-                    } else {
-                        ty_name = seg_ident;
-                    }
-                }
-            }
-            _ => {
-                // fallback: stringify the type
-                ty_name = quote! { #field.ty }.to_string();
-            }
+        let mut cfg = FieldAttrs::parse(&field.attrs);
+        // sqlx::FromRow decodes the column as text or bytes without it, failing at runtime
+        if cfg.json && !attrs::has_sqlx_json(&field.attrs) {
+            emit_error!(
+                ident,
+                "`json` field `{}` also needs `#[sqlx(json)]` (or `#[sqlx(json(nullable))]`) for sqlx::FromRow; \
+                 `#[sqlx(json)]` alone implies `naorm_cfg(json)`",
+                ident
+            );
         }
-
-        // println!("Named Field: {:?}", field);
-        field.attrs.iter().for_each(|attr| {
-            if attr.meta.path().is_ident("naorm_cfg") {
-                let toks = attr.to_token_stream().to_string();
-
-                // let toks = attr.tokens.to_string();
-                if toks.contains("auto_increment") {
-                    is_auto_increment = true;
-                    pk = ident.to_string();
-                    pk_auto_inc = true;
-                    pk_type = Some(ty_name.clone());
-                }
-                if toks.contains("primary_key") {
-                    is_primary_key = true;
-                    if pk.is_empty() {
-                        pk = ident.to_string();
-                        pk_type = Some(ty_name.clone());
-                    }
-                }
-                if toks.contains("default") {
-                    if let Some(def_idx) = toks.find("default") {
-                        if let Some(first_q_rel) = toks[def_idx..].find('"') {
-                            let first_q = def_idx + first_q_rel;
-                            if let Some(second_q_rel) = toks[first_q + 1..].find('"') {
-                                let second_q = first_q + 1 + second_q_rel;
-                                let val = &toks[first_q + 1..second_q];
-                                default_value = Some(val.to_string());
-                            }
-                        }
-                    }
-                }
-            }
-        });
-        if default_value.is_none() {
-            match ty_name.as_str() {
-                "String" => default_value = Some("".to_string()),
-                "u64" | "u32" | "u8" | "i64" | "i32" | "usize" | "isize" | "f64" | "f32" => {
-                    default_value = Some("0".to_string())
-                }
-                "float" | "float32" | "float64" => default_value = Some("0.0".to_string()),
-                "bool" => default_value = Some("false".to_string()),
-                _ => {}
-            }
+        cfg.json |= attrs::has_sqlx_json(&field.attrs);
+        let mut field_ty = types::FieldType::analyze(&field.ty);
+        if let Some(wrapped) = &cfg.wrapped {
+            field_ty.describe_as(wrapped);
+        }
+        let ty_name = field_ty.name.clone();
+        let is_option = field_ty.is_option();
+        check_conversions(ident, &cfg, &field_ty);
+        group::check_field(ident, &cfg, &field_ty);
+        if cfg.flatten && types::mentions_generics(&field_ty.inner, &input.generics) {
+            emit_error!(ident, "`flatten` field `{}` can not be of a generic type", ident);
+        }
+        let is_auto_increment = cfg.auto_increment;
+        let is_primary_key = cfg.primary_key;
+        if cfg.auto_increment {
+            pk = ident.to_string();
+            pk_auto_inc = true;
+            pk_field = Some(field);
+        }
+        if cfg.primary_key && pk.is_empty() {
+            pk = ident.to_string();
+            pk_field = Some(field);
         }
-
         let field_name = ident.to_string();
         let field_ident_token =
             syn::Ident::new(&field_name.clone(), proc_macro2::Span::call_site());
         let name_lit = LitStr::new(&ident.to_string(), proc_macro2::Span::call_site());
-        let ty_lit = LitStr::new(&ty_name, proc_macro2::Span::call_site());
+        let ty_lit = LitStr::new(&field_ty.full, proc_macro2::Span::call_site());
         let is_option_lit = if is_option {
             quote! { true }
         } else {
@@ -189,12 +389,23 @@ pub fn naorm(attr: TokenStream) -> TokenStream {
         } else {
             quote! { false }
         };
-        let default_str = default_value.unwrap_or_else(|| "".to_string());
+        let default_str = listed_default(&cfg, &field_ty);
         let default_lit = LitStr::new(&default_str, proc_macro2::Span::call_site());
-        field_tokens.push( quote! { (#name_lit, #ty_lit, #is_option_lit, #is_auto_inc_lit, #is_pk_lit, #default_lit) },);
+        field_tokens.push(quote! {
+            name: #name_lit,
+            field_type: #ty_lit,
+            is_option: #is_option_lit,
+            is_auto_increment: #is_auto_inc_lit,
+            is_primary_key: #is_pk_lit,
+            default_value: #default_lit,
+        });
 
         if !is_auto_increment {
-            insert_fields.push(field_name.clone());
+            insert_fields.push(if cfg.flatten {
+                table_create::group_names(&field_name)
+            } else {
+                field_name.clone()
+            });
             insert_field_meta.push((
                 field_name.clone(),
                 field_ident_token.clone(),
@@ -209,152 +420,102 @@ pub fn naorm(attr: TokenStream) -> TokenStream {
             is_option,
             default_str,
         ));
+        columns.push(Column {
+            name: field_name,
+            ty_name,
+            is_option,
+            indexed: cfg.index || cfg.unique,
+            enum_ty: cfg.is_enum.then(|| field_ty.inner.clone()),
+            ty: field_ty,
+            attrs: cfg,
+        });
     }
 
-    let insert_sql_string = if insert_fields.is_empty() {
-        format!("INSERT INTO {} DEFAULT VALUES", table_lit)
-    } else {
-        let placeholders = vec!["?"; insert_fields.len()].join(", ");
-        format!(
-            "INSERT INTO {} ({}) VALUES ({})",
-            table_lit,
-            insert_fields.join(", "),
-            placeholders
-        )
-    };
-    let insert_sql_lit = LitStr::new(&insert_sql_string, proc_macro2::Span::call_site());
+    let mut indexes: Vec<table_create::Index> = Vec::new();
+    for col in &columns {
+        if col.attrs.index || col.attrs.unique {
+            indexes.push(table_create::Index::new(&table_lit, None, vec![col.name.clone()], col.attrs.unique));
+        }
+    }
+    for decl in &struct_cfg.indexes {
+        for name in &decl.columns {
+            match columns.iter_mut().find(|c| &c.name == name) {
+                Some(col) => col.indexed = true,
+                None => emit_error!(input.ident, "index column `{}` is not a field of the struct", name),
+            }
+        }
+        indexes.push(table_create::Index::new(
+            &table_lit,
+            decl.name.clone(),
+            decl.columns.clone(),
+            decl.unique,
+        ));
+    }
 
-    let bind_tokens: Vec<proc_macro2::TokenStream> = insert_field_meta
+    let insert_sql_lit = target.sql(&naorm, |d| {
+        table_create::const_str(&naorm, d, &table_create::insert_sql(d, &table_lit, &insert_fields), &columns)
+    });
+
+    let json_columns: Vec<&Column> = columns.iter().filter(|c| c.attrs.json).collect();
+    let column = |name: &String| columns.iter().find(|c| &c.name == name).expect("column of a field");
+    let bind_tokens: Vec<Bind> = insert_field_meta
         .iter()
-        .map(|(_name, ident, field_type, is_option)| {
-            if *is_option {
-                if field_type.to_string() == "as_string" {
-                    quote! { .bind(self.#ident.as_deref()) }
-                } else {
-                    quote! { .bind(self.#ident.as_ref()) }
-                }
-            } else {
-                if field_type.to_string() == "as_string" {
-                    quote! { .bind(self.#ident.as_str()) }
-                } else {
-                    quote! { .bind(&self.#ident) }
-                }
-            }
+        .map(|(name, ident, field_type, _is_option)| match column(name) {
+            col if col.attrs.flatten => Bind::Group(ident.clone()),
+            col => Bind::Call(bind_field(ident, field_type, col)),
         })
         .collect();
 
-    let update_bind_tokens: Vec<proc_macro2::TokenStream> = {
-        let mut toks: Vec<proc_macro2::TokenStream> = Vec::new();
-        for (_name, ident, field_type, is_option) in &insert_field_meta {
-            if *is_option {
-                if field_type.to_string() == "as_string" {
-                    toks.push(quote! { .bind(self.#ident.as_deref()) });
-                } else {
-                    toks.push(quote! { .bind(self.#ident.as_ref()) });
-                }
-            } else {
-                if field_type.to_string() == "as_string" {
-                    toks.push(quote! { .bind(self.#ident.as_str()) });
-                } else {
-                    toks.push(quote! { .bind(&self.#ident) });
-                }
-            }
-        }
-        if !pk.is_empty() {
-            // let pk_field_type_opt = fields_type
-            //     .iter()
-            //     .find(|(n, _id, _ft, _opt, _d)| n == &pk)
-            //     .map(|(_n, _id, ft, _opt, _d)| ft.to_string());
-            let pk_ident = syn::Ident::new(&pk, proc_macro2::Span::call_site());
-            // bind primary key (handle Option and String similarly if necessary)
-            // try to find pk meta in insert_field_meta to know is_option/field_type
-            let pk_meta = insert_field_meta
-                .iter()
-                .find(|(n, _id, _ft, _opt)| n == &pk);
-            match pk_meta {
-                Some((_n, _id, ft, is_opt)) => {
-                    if *is_opt {
-                        if ft.to_string() == "as_string" {
-                            toks.push(quote! { .bind(self.#pk_ident.as_deref()) });
-                        } else {
-                            toks.push(quote! { .bind(self.#pk_ident.as_ref()) });
-                        }
-                    } else {
-                        if ft.to_string() == "as_string" {
-                            toks.push(quote! { .bind(self.#pk_ident.as_str()) });
-                        } else {
-                            toks.push(quote! { .bind(&self.#pk_ident) });
-                        }
-                    }
-                }
-                None => toks.push(quote! { .bind(&self.#pk_ident) }),
-            }
-        }
-        toks
-    };
+    // the primary key, bound last by UPDATE and alone by DELETE
+    let pk_bind: Option<proc_macro2::TokenStream> = fields_type
+        .iter()
+        .find(|(n, _id, _ft, _opt, _d)| n == &pk)
+        .map(|(n, ident, ft, _opt, _d)| bind_field(ident, ft, column(n)));
 
-    let update_sql_string = if insert_fields.is_empty() {
-        format!("-- No fields to update for table {}", table_lit)
-    } else {
-        let set_clauses: Vec<String> = insert_fields
-            .iter()
-            .map(|field| format!("{} = ?", field))
-            .collect();
-        format!(
-            "UPDATE {} SET {} WHERE {} = ?",
-            table_lit,
-            set_clauses.join(", "),
-            pk
-        )
-    };
+    let update_bind_tokens: Vec<Bind> = insert_field_meta
+        .iter()
+        .map(|(name, ident, field_type, _is_option)| match column(name) {
+            col if col.attrs.flatten => Bind::Group(ident.clone()),
+            col => Bind::Call(bind_field(ident, field_type, col)),
+        })
+        .chain(pk_bind.clone().map(Bind::Call))
+        .collect();
 
-    let update_sql_lit = LitStr::new(&update_sql_string, proc_macro2::Span::call_site());
+    let update_sql_lit = target.sql(&naorm, |d| {
+        if insert_fields.is_empty() {
+            sql_lit(format!("-- No fields to update for table {}", table_lit))
+        } else {
+            table_create::const_str(&naorm, d, &table_create::update_sql(d, &table_lit, &insert_fields, &pk), &columns)
+        }
+    });
 
-    let delete_sql_string = if pk.is_empty() {
-        format!("-- No PK for table {}", table_lit)
-    } else {
-        format!("DELETE FROM {} WHERE {} = ?", table_lit, pk)
-    };
-    let delete_sql_lit = LitStr::new(&delete_sql_string, proc_macro2::Span::call_site());
+    let delete_sql_lit = target.sql(&naorm, |d| {
+        sql_lit(if pk.is_empty() {
+            format!("-- No PK for table {}", table_lit)
+        } else {
+            table_create::delete_sql(d, &table_lit, &[pk.clone()])
+        })
+    });
 
-    let delete_bind_tokens: Vec<proc_macro2::TokenStream> = if pk.is_empty() {
-        Vec::new()
-    } else {
-        let pk_ident = syn::Ident::new(&pk, proc_macro2::Span::call_site());
-        match insert_field_meta
-            .iter()
-            .find(|(n, _id, _ft, _opt)| n == &pk)
-        {
-            Some((_n, _id, ft, is_opt)) => {
-                if *is_opt {
-                    if ft.to_string() == "as_string" {
-                        vec![quote! { .bind(self.#pk_ident.as_deref()) }]
-                    } else {
-                        vec![quote! { .bind(self.#pk_ident.as_ref()) }]
-                    }
-                } else {
-                    if ft.to_string() == "as_string" {
-                        vec![quote! { .bind(self.#pk_ident.as_str()) }]
-                    } else {
-                        vec![quote! { .bind(&self.#pk_ident) }]
-                    }
-                }
-            }
-            None => vec![quote! { .bind(&self.#pk_ident) }],
-        }
-    };
+    let delete_bind_tokens: Vec<Bind> = pk_bind.into_iter().map(Bind::Call).collect();
 
     // select tokens: build SELECT_SQL and optional bind tokens (no binds for simple select *)
     let select_fields: Vec<String> = fields_type
         .iter()
-        .map(|(n, _id, _ft, _opt, _d)| n.clone())
+        .map(|(n, _id, _ft, _opt, _d)| {
+            if column(n).attrs.flatten {
+                table_create::group_names(n)
+            } else {
+                n.clone()
+            }
+        })
         .collect();
     let select_sql_string = if select_fields.is_empty() {
         format!("SELECT * FROM {}", table_lit)
     } else {
         format!("SELECT {} FROM {}", select_fields.join(", "), table_lit)
     };
-    let select_sql_lit = LitStr::new(&select_sql_string, proc_macro2::Span::call_site());
+    let select_sql_lit = table_create::const_str(&naorm, driver, &select_sql_string, &columns);
 
     // For now there are no default select bind tokens (specific query methods may add them later)
 
@@ -366,65 +527,488 @@ pub fn naorm(attr: TokenStream) -> TokenStream {
         quote! { false }
     };
 
-    // driver
-    let mut driver_lit = quote! { sqlx::Sqlite};
-    let mut driver_arg_lit = quote! { sqlx::sqlite::SqliteArguments};
-    let mut driver_row_lit = quote! { sqlx::sqlite::SqliteRow};
-    match driver {
-        Some(ref d) if d.to_lowercase() == "sqlite" => {
-            // already set
-            driver_lit = quote! { sqlx::Sqlite};
-            driver_arg_lit = quote! { sqlx::sqlite::SqliteArguments};
-            driver_row_lit = quote! { sqlx::sqlite::SqliteRow};
-        }
-        Some(ref d) if d.to_lowercase() == "mysql" => {
-            driver_lit = quote! { sqlx::MySql};
-            driver_arg_lit = quote! { sqlx::mysql::MySqlArguments};
-            driver_row_lit = quote! { sqlx::mysql::MySqlRow};
-        }
-        Some(ref d) if d.to_lowercase() == "postgres" => {
-            driver_lit = quote! { sqlx::Postgres};
-            driver_arg_lit = quote! { sqlx::postgres::PgArguments};
-            driver_row_lit = quote! { sqlx::postgres::PgRow};
-        }
-        Some(ref d) => {
-            emit_error!(input.ident, format!("Unsupported driver specified: {}", d));
-        }
-        None => {
-            // default to sqlite
+    let driver_lit = target.db();
+    let driver_arg_lit = target.arguments(quote! { 'q });
+    let driver_arg_static = target.arguments(quote! { 'static });
+    let driver_row_lit = target.row();
+    let sql_ty = target.sql_type(&naorm);
+    // relations, search and json paths are generated for one driver, and `sqlx::Any`
+    // can not encode enums
+    if !matches!(target, Target::One(_)) {
+        for col in &columns {
+            let options = [
+                (col.attrs.belongs_to.is_some(), "belongs_to"),
+                (col.attrs.fulltext, "fulltext"),
+                (col.attrs.json, "json"),
+                (col.attrs.is_enum && target.is_any(), "enum"),
+                (col.attrs.flatten && target.is_any(), "flatten"),
+            ];
+            for (_, option) in options.iter().filter(|(set, _)| *set) {
+                emit_error!(
+                    input.ident,
+                    "`{}` of field `{}` is not supported with {}",
+                    option,
+                    col.name,
+                    target.describe()
+                );
+            }
+        }
+        if !struct_cfg.relations.is_empty() || !struct_cfg.many_to_many.is_empty() {
+            emit_error!(input.ident, "relations are not supported with {}", target.describe());
         }
     }
 
-    // 生成代码：保留原始 struct，并为其生成常量/方法
-    let expanded = quote! {
-        impl #struct_ident {
-            pub const PK : &'static str = #pk;
-            pub const PK_AUTO_INCREMENT : bool = #pk_auto_inc_lit;
-            pub const NAORM_TABLE: &'static str = #table_lit;
-            pub const NAORM_DB: &'static str = #db_lit;
-            pub const NAORM_TABLE_TYPE: &'static str = #table_type_lit;
-            pub const SELECT_SQL: &'static str = #select_sql_lit;
-            pub const INSERT_SQL: &'static str = #insert_sql_lit;
-            pub const UPDATE_SQL: &'static str = #update_sql_lit;
-            pub const DELETE_SQL: &'static str = #delete_sql_lit;
-            // file_name , field_type, is_option, is_auto_increment, is_primary_key, default_value
-            pub const NAORM_FIELDS: &'static [(&'static str, &'static str, bool, bool, bool, &'static str)] = &[
-                #(#field_tokens),*
-            ];
-            pub fn insert_query<'q>(&'q mut self) -> sqlx::query::Query<'q, #driver_lit, #driver_arg_lit<'q>> {
-                sqlx::query(Self::INSERT_SQL)
-                    #(#bind_tokens)*
+    // the json paths and the search queries list the columns of the struct
+    let flatten_columns: Vec<&Column> = columns.iter().filter(|c| c.attrs.flatten).collect();
+    if !flatten_columns.is_empty() && columns.iter().any(|c| c.attrs.json || c.attrs.fulltext) {
+        emit_error!(input.ident, "`flatten` fields can not be combined with `json` or `fulltext` fields");
+    }
+
+    let belongs_to: Vec<BelongsTo> = columns.iter().filter_map(BelongsTo::from_column).collect();
+    let foreign_keys: Vec<String> = belongs_to.iter().map(|r| r.foreign_key_sql()).collect();
+    let mut relation_tokens: Vec<proc_macro2::TokenStream> =
+        belongs_to.iter().map(|r| r.methods(driver)).collect();
+    if !struct_cfg.relations.is_empty() {
+        match (pk_field, columns.iter().find(|c| c.name == pk)) {
+            (Some(pk_field), Some(pk_col)) => {
+                for rel in &struct_cfg.relations {
+                    relation_tokens.push(relations::has_methods(
+                        rel,
+                        &struct_ident.to_string(),
+                        driver,
+                        &naorm,
+                        pk_field,
+                        pk_col,
+                    ));
+                }
+            }
+            _ => emit_error!(input.ident, "has_many/has_one require a primary_key field"),
+        }
+    }
+    if !struct_cfg.many_to_many.is_empty() {
+        match (pk_field, columns.iter().find(|c| c.name == pk)) {
+            (Some(pk_field), Some(pk_col)) => {
+                for rel in &struct_cfg.many_to_many {
+                    relation_tokens.push(relations::many_to_many_methods(
+                        rel, driver, &naorm, pk_field, pk_col,
+                    ));
+                }
+            }
+            _ => emit_error!(input.ident, "many_to_many requires a primary_key field"),
+        }
+    }
+    let prefixed_fields = |db: &proc_macro2::TokenStream| -> Vec<proc_macro2::TokenStream> {
+        fields_type
+            .iter()
+            .map(|(name, ident, _ft, _is_option, _d)| decode_field(&naorm, db, column(name), ident))
+            .collect()
+    };
+    let json_methods: Vec<proc_macro2::TokenStream> = json_columns
+        .iter()
+        .map(|c| json::path_query(driver, &naorm, &select_sql_string, &c.name))
+        .collect();
+    // the column type is known once the indexes are
+    let field_tokens: Vec<proc_macro2::TokenStream> = field_tokens
+        .iter()
+        .zip(&columns)
+        .map(|(field, col)| {
+            if col.attrs.flatten {
+                return group::fields(&naorm, driver, col);
+            }
+            // `any` has one type per dialect, see `schema_for(backend)`; several drivers
+            // list the first
+            let sql_type = match target {
+                Target::Any => quote! { "" },
+                _ => table_create::const_str(&naorm, driver, &table_create::ddl_type(driver, col), &columns),
+            };
+            let entry = quote! { #naorm::FieldInfo { #field sql_type: #sql_type } };
+            if flatten_columns.is_empty() { entry } else { quote! { &[#entry] } }
+        })
+        .collect();
+    // the columns of `flatten` fields are only known to the compiler
+    let (naorm_fields, model_columns) = if flatten_columns.is_empty() {
+        (quote! { &[#(#field_tokens),*] }, quote! { &[#(#select_fields),*] })
+    } else {
+        let names: Vec<proc_macro2::TokenStream> = columns
+            .iter()
+            .map(|col| {
+                if col.attrs.flatten {
+                    let names = group::names(&naorm, col);
+                    quote! { &#names }
+                } else {
+                    let name = &col.name;
+                    quote! { &[#name] }
+                }
+            })
+            .collect();
+        let field_tokens: Vec<proc_macro2::TokenStream> = field_tokens
+            .iter()
+            .zip(&columns)
+            .map(|(field, col)| if col.attrs.flatten { quote! { &#field } } else { field.clone() })
+            .collect();
+        (
+            group::concat(
+                &naorm,
+                quote! { #naorm::FieldInfo },
+                &field_tokens,
+                quote! { #naorm::FieldInfo::EMPTY },
+            ),
+            group::concat(&naorm, quote! { &'static str }, &names, quote! { "" }),
+        )
+    };
+    let schema_tokens = schema::schema_tokens(&naorm, target, &columns, &indexes);
+    let verify_schema_tokens = schema::verify_schema_tokens(&naorm, target);
+    let schema_for_tokens = schema::schema_for_tokens(&naorm, target, &columns, &indexes);
+    let (model_key_ty, model_key) = match pk_field {
+        Some(f) => {
+            let (ty, ident) = (&f.ty, &f.ident);
+            (quote! { #ty }, quote! { &self.#ident })
+        }
+        None => (quote! { () }, quote! { &() }),
+    };
+    let relation_assertions: Vec<proc_macro2::TokenStream> =
+        belongs_to.iter().map(|r| r.assertions(&naorm)).collect();
+
+    let create_index_sql: Vec<proc_macro2::TokenStream> = indexes
+        .iter()
+        .map(|i| target.sql(&naorm, |d| sql_lit(table_create::create_index_sql(d, &table_lit, i))))
+        .collect();
+    let fulltext_columns: Vec<String> = columns
+        .iter()
+        .filter(|c| c.attrs.fulltext)
+        .map(|c| c.name.clone())
+        .collect();
+    for col in columns.iter().filter(|c| c.attrs.fulltext && !c.is_text()) {
+        emit_error!(input.ident, "fulltext field `{}` must be a String", col.name);
+    }
+    let fulltext = (!fulltext_columns.is_empty()).then(|| fulltext::FullText {
+        table: &table_lit,
+        columns: fulltext_columns,
+        select: &select_fields,
+    });
+    let fulltext_methods = fulltext.as_ref().map(|f| f.methods(driver, &naorm));
+    let create_all_sql_lit = target.sql(&naorm, |d| {
+        let create_table_sql = table_create::create_table_sql(d, &table_lit, &columns, &foreign_keys);
+        let fulltext_sql = fulltext.as_ref().map(|f| f.statements(d)).unwrap_or_default();
+        let sql = table_create::create_all_sql(d, &table_lit, &create_table_sql, &indexes, &fulltext_sql);
+        table_create::const_str(&naorm, d, &sql, &columns)
+    });
+    let create_table_sql_lit = target.sql(&naorm, |d| {
+        let create_table_sql = table_create::create_table_sql(d, &table_lit, &columns, &foreign_keys);
+        table_create::const_str(&naorm, d, &create_table_sql, &columns)
+    });
+    // fields of generic type get the bounds of their binds (inherent impl) and of
+    // `from_row_prefixed` (`Model` impl); borrowed text and slices bind through a
+    // deref and `Cow`s decode owned
+    let generic_columns: Vec<&Column> = columns
+        .iter()
+        .filter(|c| !c.attrs.flatten && types::mentions_generics(&c.ty.inner, &input.generics))
+        .collect();
+    let custom_bind = |col: &Column| col.attrs.transparent || col.attrs.bind_with.is_some();
+    let custom_decode = |col: &Column| col.attrs.transparent || col.attrs.decode_with.is_some();
+    let serde = quote! { #naorm::__private::serde };
+    let mut generics = input.generics.clone();
+    for col in &generic_columns {
+        let ty = &col.ty.inner;
+        if col.attrs.json {
+            generics.make_where_clause().predicates.push(parse_quote! { #ty: #serde::Serialize });
+        // several drivers bound the binds per method
+        } else if !col.ty.via_vec && !col.ty.is_borrowed() && !custom_bind(col) && !matches!(target, Target::Many(_)) {
+            generics.make_where_clause().predicates.push(parse_quote! {
+                #ty: for<'q> sqlx::Encode<'q, #driver_lit> + sqlx::Type<#driver_lit>
+            });
+        }
+    }
+    let add_decode_bounds = |generics: &mut syn::Generics, db: &proc_macro2::TokenStream| {
+        for col in &generic_columns {
+            let ty = &col.ty.inner;
+            if col.attrs.json {
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(parse_quote! { #ty: #serde::de::DeserializeOwned });
+            } else if !col.ty.via_vec && col.ty.cow.is_none() && !custom_decode(col) {
+                generics.make_where_clause().predicates.push(parse_quote! {
+                    #ty: for<'r> sqlx::Decode<'r, #db> + sqlx::Type<#db>
+                });
             }
-            pub fn update_query<'q>(&'q mut self) -> sqlx::query::Query<'q, #driver_lit, #driver_arg_lit<'q>> {
-                sqlx::query(Self::UPDATE_SQL)
-                    #(#update_bind_tokens)*
+        }
+    };
+    let mut model_generics = generics.clone();
+    add_decode_bounds(&mut model_generics, &driver_lit);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (model_impl_generics, _, model_where_clause) = model_generics.split_for_impl();
+    let (plain_impl_generics, _, plain_where_clause) = input.generics.split_for_impl();
+
+    let new_default_tokens = defaults::new_default_tokens(&naorm, &columns);
+    let default_impl = struct_cfg.impl_default.then(|| {
+        quote! {
+            impl #impl_generics ::core::default::Default for #struct_ident #ty_generics #where_clause {
+                fn default() -> Self {
+                    Self::new_default()
+                }
+            }
+        }
+    });
+    let validate_tokens: Vec<proc_macro2::TokenStream> = columns
+        .iter()
+        .map(|col| {
+            if col.attrs.flatten {
+                let ident = format_ident!("{}", col.name);
+                return quote! { #naorm::ColumnGroup::validate_columns(&self.#ident, &mut errors); };
+            }
+            validate::column_checks(&naorm, col)
+        })
+        .collect();
+
+    // types bound by the queries of several drivers, generic over the database
+    let mut bind_bounds: Vec<proc_macro2::TokenStream> = Vec::new();
+    let mut group_types: Vec<&syn::Type> = Vec::new();
+    for col in &columns {
+        if col.attrs.flatten {
+            group_types.push(&col.ty.inner);
+            continue;
+        }
+        if let Target::Many(_) = target
+            && (col.attrs.bind_with.is_some() || (col.attrs.transparent && col.attrs.wrapped.is_none()))
+        {
+            emit_error!(
+                input.ident,
+                "field `{}` needs a bind type for {}: use `transparent = \"...\"` instead",
+                col.name,
+                target.describe()
+            );
+        }
+        let value = if let Some(wrapped) = &col.attrs.wrapped {
+            quote! { &'q #wrapped }
+        } else if col.ty.via_vec || (col.ty.bytes && col.ty.is_borrowed()) {
+            quote! { &'q [u8] }
+        } else if col.ty_name == "String" || col.ty_name == "str" {
+            quote! { &'q str }
+        } else {
+            let ty = &col.ty.inner;
+            quote! { &'q #ty }
+        };
+        let bound = if col.is_option {
+            quote! { Option<#value> }
+        } else {
+            value
+        };
+        if !bind_bounds.iter().any(|b| b.to_string() == bound.to_string()) {
+            bind_bounds.push(bound);
+        }
+    }
+    let supports_impls: Vec<proc_macro2::TokenStream> = match target {
+        Target::Many(drivers) => drivers
+            .iter()
+            .map(|d| {
+                let (gate, db) = (d.gate(&naorm), d.db());
+                quote! {
+                    #gate! {
+                        impl #plain_impl_generics #naorm::dialect::Supports<#db> for #struct_ident #ty_generics #plain_where_clause {}
+                    }
+                }
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    // `any` structs pick the dialect from the connection, or take it as an argument;
+    // several drivers make the methods generic over the database
+    let query = |sql: proc_macro2::TokenStream, binds: &[Bind]| chain_binds(&naorm, quote! { sqlx::query(#sql) }, binds);
+    let write_methods = match target {
+        Target::One(_) => {
+            let one_insert = query(quote! { Self::INSERT_SQL }, &bind_tokens);
+            let one_update = query(quote! { Self::UPDATE_SQL }, &update_bind_tokens);
+            let one_delete = query(quote! { Self::DELETE_SQL }, &delete_bind_tokens);
+            quote! {
+                pub async fn create_table<'e, E>(executor: E) -> Result<(), sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #driver_lit>,
+                {
+                    sqlx::raw_sql(#create_all_sql_lit).execute(executor).await?;
+                    Ok(())
+                }
+
+                #verify_schema_tokens
+
+                /// Validates and inserts the value.
+                pub async fn insert<'e, E>(&mut self, executor: E) -> Result<<#driver_lit as sqlx::Database>::QueryResult, #naorm::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #driver_lit>,
+                {
+                    self.validate()?;
+                    Ok(self.insert_query().execute(executor).await?)
+                }
+
+                /// Validates the value and updates its row by primary key.
+                pub async fn update<'e, E>(&mut self, executor: E) -> Result<<#driver_lit as sqlx::Database>::QueryResult, #naorm::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #driver_lit>,
+                {
+                    self.validate()?;
+                    Ok(self.update_query().execute(executor).await?)
+                }
+
+                pub fn insert_query<'q>(&'q mut self) -> sqlx::query::Query<'q, #driver_lit, #driver_arg_lit> {
+                    #one_insert
+                }
+                pub fn update_query<'q>(&'q mut self) -> sqlx::query::Query<'q, #driver_lit, #driver_arg_lit> {
+                    #one_update
+                }
+                pub fn delete_query<'q>(&'q self) -> sqlx::query::Query<'q, #driver_lit, #driver_arg_lit> {
+                    #one_delete
+                }
+            }
+        }
+        Target::Any => {
+            let any_insert = query(quote! { Self::INSERT_SQL.get(backend) }, &bind_tokens);
+            let any_update = query(quote! { Self::UPDATE_SQL.get(backend) }, &update_bind_tokens);
+            let any_delete = query(quote! { Self::DELETE_SQL.get(backend) }, &delete_bind_tokens);
+            quote! {
+                pub async fn create_table<'c, A>(conn: A) -> Result<(), sqlx::Error>
+                where
+                    A: sqlx::Acquire<'c, Database = sqlx::Any>,
+                {
+                    let mut conn = conn.acquire().await?;
+                    let backend = #naorm::Backend::of_any(&conn)?;
+                    sqlx::raw_sql(#create_all_sql_lit.get(backend)).execute(&mut *conn).await?;
+                    Ok(())
+                }
+
+                #verify_schema_tokens
+
+                /// Validates and inserts the value, in the dialect of the connection.
+                pub async fn insert<'c, A>(&mut self, conn: A) -> Result<sqlx::any::AnyQueryResult, #naorm::Error>
+                where
+                    A: sqlx::Acquire<'c, Database = sqlx::Any>,
+                {
+                    self.validate()?;
+                    let mut conn = conn.acquire().await?;
+                    let backend = #naorm::Backend::of_any(&conn)?;
+                    Ok(self.insert_query(backend).execute(&mut *conn).await?)
+                }
+
+                /// Validates the value and updates its row by primary key, in the dialect of
+                /// the connection.
+                pub async fn update<'c, A>(&mut self, conn: A) -> Result<sqlx::any::AnyQueryResult, #naorm::Error>
+                where
+                    A: sqlx::Acquire<'c, Database = sqlx::Any>,
+                {
+                    self.validate()?;
+                    let mut conn = conn.acquire().await?;
+                    let backend = #naorm::Backend::of_any(&conn)?;
+                    Ok(self.update_query(backend).execute(&mut *conn).await?)
+                }
+
+                pub fn insert_query<'q>(&'q mut self, backend: #naorm::Backend) -> sqlx::query::Query<'q, sqlx::Any, #driver_arg_lit> {
+                    #any_insert
+                }
+                pub fn update_query<'q>(&'q mut self, backend: #naorm::Backend) -> sqlx::query::Query<'q, sqlx::Any, #driver_arg_lit> {
+                    #any_update
+                }
+                pub fn delete_query<'q>(&'q self, backend: #naorm::Backend) -> sqlx::query::Query<'q, sqlx::Any, #driver_arg_lit> {
+                    #any_delete
+                }
             }
-            pub fn delete_query<'q>(&'q self) -> sqlx::query::Query<'q, #driver_lit, #driver_arg_lit<'q>> {
-                sqlx::query(Self::DELETE_SQL)
-                    #(#delete_bind_tokens)*
+        }
+        Target::Many(_) => {
+            let many_insert = query(quote! { Self::INSERT_SQL.get(DB::BACKEND) }, &bind_tokens);
+            let many_update = query(quote! { Self::UPDATE_SQL.get(DB::BACKEND) }, &update_bind_tokens);
+            let many_delete = query(quote! { Self::DELETE_SQL.get(DB::BACKEND) }, &delete_bind_tokens);
+            let db = quote! { E::Database };
+            let dialect = quote! {
+                E::Database: #naorm::NaormDialect,
+                Self: #naorm::dialect::Supports<E::Database>,
+            };
+            let query_bounds = quote! {
+                DB: #naorm::NaormDialect,
+                Self: #naorm::dialect::Supports<DB>,
+                #(#bind_bounds: sqlx::Encode<'q, DB> + sqlx::Type<DB>,)*
+                #(#group_types: #naorm::group::GroupCodec<DB>,)*
+            };
+            quote! {
+                pub async fn create_table<'e, E>(executor: E) -> Result<(), sqlx::Error>
+                where
+                    E: sqlx::Executor<'e>,
+                    #dialect
+                {
+                    let backend = <#db as #naorm::NaormDialect>::BACKEND;
+                    sqlx::raw_sql(#create_all_sql_lit.get(backend)).execute(executor).await?;
+                    Ok(())
+                }
+
+                #verify_schema_tokens
+
+                /// Validates and inserts the value.
+                pub async fn insert<'e, E>(&mut self, executor: E) -> Result<<#db as sqlx::Database>::QueryResult, #naorm::Error>
+                where
+                    E: sqlx::Executor<'e>,
+                    #dialect
+                    for<'q> <#db as sqlx::Database>::Arguments<'q>: sqlx::IntoArguments<'q, #db>,
+                    #(for<'q> #bind_bounds: sqlx::Encode<'q, #db> + sqlx::Type<#db>,)*
+                    #(#group_types: #naorm::group::GroupCodec<#db>,)*
+                {
+                    self.validate()?;
+                    Ok(self.insert_query::<#db>().execute(executor).await?)
+                }
+
+                /// Validates the value and updates its row by primary key.
+                pub async fn update<'e, E>(&mut self, executor: E) -> Result<<#db as sqlx::Database>::QueryResult, #naorm::Error>
+                where
+                    E: sqlx::Executor<'e>,
+                    #dialect
+                    for<'q> <#db as sqlx::Database>::Arguments<'q>: sqlx::IntoArguments<'q, #db>,
+                    #(for<'q> #bind_bounds: sqlx::Encode<'q, #db> + sqlx::Type<#db>,)*
+                    #(#group_types: #naorm::group::GroupCodec<#db>,)*
+                {
+                    self.validate()?;
+                    Ok(self.update_query::<#db>().execute(executor).await?)
+                }
+
+                pub fn insert_query<'q, DB>(&'q mut self) -> sqlx::query::Query<'q, DB, <DB as sqlx::Database>::Arguments<'q>>
+                where
+                    #query_bounds
+                {
+                    #many_insert
+                }
+                pub fn update_query<'q, DB>(&'q mut self) -> sqlx::query::Query<'q, DB, <DB as sqlx::Database>::Arguments<'q>>
+                where
+                    #query_bounds
+                {
+                    #many_update
+                }
+                pub fn delete_query<'q, DB>(&'q self) -> sqlx::query::Query<'q, DB, <DB as sqlx::Database>::Arguments<'q>>
+                where
+                    #query_bounds
+                {
+                    #many_delete
+                }
             }
+        }
+    };
 
-            pub fn all_query() -> sqlx::query::QueryAs<'static, #driver_lit, Self, #driver_arg_lit<'static>>
+    let read_methods = match target {
+        Target::Many(_) => quote! {
+            pub fn all_query<DB>() -> sqlx::query::QueryAs<'static, DB, Self, <DB as sqlx::Database>::Arguments<'static>>
+            where
+                DB: #naorm::NaormDialect,
+                Self: #naorm::dialect::Supports<DB> + for<'r> sqlx::FromRow<'r, DB::Row>,
+            {
+                sqlx::query_as::<DB, Self>(Self::SELECT_SQL)
+            }
+            pub fn filter_query<'q, DB>(
+                w: &'q str,
+            ) -> sqlx::query::QueryAs<'q, DB, Self, <DB as sqlx::Database>::Arguments<'q>>
+            where
+                DB: #naorm::NaormDialect,
+                Self: #naorm::dialect::Supports<DB> + for<'r> sqlx::FromRow<'r, DB::Row>,
+            {
+                sqlx::query_as::<DB, Self>(w)
+            }
+        },
+        _ => quote! {
+            pub fn all_query() -> sqlx::query::QueryAs<'static, #driver_lit, Self, #driver_arg_static>
             where
                 Self: for<'r> sqlx::FromRow<'r, #driver_row_lit>,
             {
@@ -432,14 +1016,187 @@ pub fn naorm(attr: TokenStream) -> TokenStream {
             }
             pub fn filter_query<'q>(
                 w: &'q str,
-            ) -> sqlx::query::QueryAs<'q, #driver_lit, Self, #driver_arg_lit<'q>>
+            ) -> sqlx::query::QueryAs<'q, #driver_lit, Self, #driver_arg_lit>
             where
                 Self: for<'r> sqlx::FromRow<'r, #driver_row_lit>,
             {
                 sqlx::query_as::<#driver_lit, Self>(w)
             }
+        },
+    };
+
+    let model_fields = prefixed_fields(&driver_lit);
+    // `sqlx::FromRow` can not be derived for structs with `flatten` fields
+    let from_row_drivers: Vec<Driver> = match target {
+        _ if flatten_columns.is_empty() => Vec::new(),
+        Target::Many(drivers) => drivers.iter().collect(),
+        _ => vec![driver],
+    };
+    let from_row_impls: Vec<proc_macro2::TokenStream> = from_row_drivers
+        .iter()
+        .map(|d| {
+            let (db, row) = (d.db(), d.row());
+            let fields = prefixed_fields(&db);
+            let mut from_row_generics = input.generics.clone();
+            add_decode_bounds(&mut from_row_generics, &db);
+            from_row_generics.params.insert(0, parse_quote! { 'r });
+            let (impl_generics, _, where_clause) = from_row_generics.split_for_impl();
+            let from_row = quote! {
+                impl #impl_generics sqlx::FromRow<'r, #row> for #struct_ident #ty_generics #where_clause {
+                    fn from_row(row: &'r #row) -> Result<Self, sqlx::Error> {
+                        let prefix = "";
+                        Ok(Self {
+                            #(#fields)*
+                        })
+                    }
+                }
+            };
+            match target {
+                Target::Many(_) => {
+                    let gate = d.gate(&naorm);
+                    quote! { #gate! { #from_row } }
+                }
+                _ => from_row,
+            }
+        })
+        .collect();
+
+    let columns_struct = aggregate::columns_struct(&naorm, &input, &columns);
+    let aggregate_methods = aggregate::methods(&naorm);
+    let stream_methods = match (pk_field, columns.iter().find(|c| c.name == pk)) {
+        (Some(field), Some(col)) => stream::methods(&naorm, target, &field.ty, Some(col)),
+        _ => stream::methods(&naorm, target, &parse_quote! { () }, None),
+    };
+
+    // writes of a `UnitOfWork`, one impl per concrete driver
+    let references: Vec<&str> = belongs_to.iter().map(|r| r.table()).collect();
+    let persist_drivers: Vec<Driver> = match target {
+        Target::Any => Vec::new(),
+        Target::One(d) => vec![d],
+        Target::Many(drivers) => drivers.iter().collect(),
+    };
+    let persist_impls: Vec<proc_macro2::TokenStream> = persist_drivers
+        .iter()
+        .map(|d| {
+            let (db, args) = (d.db(), d.arguments(quote! { 'q }));
+            let mut persist_generics = model_generics.clone();
+            let (insert, update, delete) = match target {
+                Target::Many(_) => {
+                    let predicates = persist_generics.make_where_clause();
+                    for ty in &bind_bounds {
+                        predicates.predicates.push(parse_quote! { for<'q> #ty: sqlx::Encode<'q, #db> + sqlx::Type<#db> });
+                    }
+                    for ty in &group_types {
+                        predicates.predicates.push(parse_quote! { #ty: #naorm::group::GroupCodec<#db> });
+                    }
+                    (
+                        quote! { self.insert_query::<#db>() },
+                        quote! { self.update_query::<#db>() },
+                        quote! { self.delete_query::<#db>() },
+                    )
+                }
+                _ => (quote! { self.insert_query() }, quote! { self.update_query() }, quote! { self.delete_query() }),
+            };
+            let (persist_impl_generics, _, persist_where_clause) = persist_generics.split_for_impl();
+            let persist = quote! {
+                impl #persist_impl_generics #naorm::Persist<#db> for #struct_ident #ty_generics #persist_where_clause {
+                    const REFERENCES: &'static [&'static str] = &[#(#references),*];
+                    const GENERATED_KEY: bool = #pk_auto_inc_lit;
+
+                    fn validate(&self) -> Result<(), #naorm::ValidationErrors> {
+                        Self::validate(self)
+                    }
+                    fn insert_query<'q>(&'q mut self) -> sqlx::query::Query<'q, #db, #args> {
+                        #insert
+                    }
+                    fn update_query<'q>(&'q mut self) -> sqlx::query::Query<'q, #db, #args> {
+                        #update
+                    }
+                    fn delete_query<'q>(&'q self) -> sqlx::query::Query<'q, #db, #args> {
+                        #delete
+                    }
+                }
+            };
+            match target {
+                Target::Many(_) => {
+                    let gate = d.gate(&naorm);
+                    quote! { #gate! { #persist } }
+                }
+                _ => persist,
+            }
+        })
+        .collect();
+
+    // 生成代码：保留原始 struct，并为其生成常量/方法
+    let expanded = quote! {
+        impl #impl_generics #struct_ident #ty_generics #where_clause {
+            pub const PK : &'static str = #pk;
+            pub const PK_AUTO_INCREMENT : bool = #pk_auto_inc_lit;
+            pub const NAORM_TABLE: &'static str = #table_lit;
+            pub const NAORM_DB: &'static str = #db_lit;
+            pub const NAORM_TABLE_TYPE: &'static str = #table_type_lit;
+            pub const SELECT_SQL: &'static str = #select_sql_lit;
+            pub const INSERT_SQL: #sql_ty = #insert_sql_lit;
+            pub const UPDATE_SQL: #sql_ty = #update_sql_lit;
+            pub const DELETE_SQL: #sql_ty = #delete_sql_lit;
+            pub const CREATE_TABLE_SQL: #sql_ty = #create_table_sql_lit;
+            /// One statement per declared index (without `IF NOT EXISTS` on mysql).
+            pub const CREATE_INDEX_SQL: &'static [#sql_ty] = &[#(#create_index_sql),*];
+            pub const NAORM_FIELDS: &'static [#naorm::FieldInfo] =
+                #naorm_fields;
+
+            /// Checks the `naorm_cfg` validation rules of every field.
+            pub fn validate(&self) -> Result<(), #naorm::ValidationErrors> {
+                #[allow(unused_mut)]
+                let mut errors = #naorm::ValidationErrors::new();
+                #(#validate_tokens)*
+                errors.into_result()
+            }
+
+            #write_methods
+
+            #read_methods
+            #stream_methods
+
+            #new_default_tokens
+            #schema_for_tokens
+            #aggregate_methods
+
+            #(#relation_tokens)*
+            #fulltext_methods
+            #(#json_methods)*
+        }
+
+        impl #model_impl_generics #naorm::Model for #struct_ident #ty_generics #model_where_clause {
+            type Database = #driver_lit;
+            type Key = #model_key_ty;
+            const TABLE: &'static str = Self::NAORM_TABLE;
+            const PK: &'static str = Self::PK;
+            const SELECT_SQL: &'static str = Self::SELECT_SQL;
+            const COLUMNS: &'static [&'static str] = #model_columns;
+
+            fn key(&self) -> &Self::Key {
+                #model_key
+            }
+
+            fn schema() -> #naorm::schema::TableSchema {
+                #schema_tokens
+            }
+
+            fn from_row_prefixed(row: &#driver_row_lit, prefix: &str) -> Result<Self, sqlx::Error> {
+                Ok(Self {
+                    #(#model_fields)*
+                })
+            }
         }
 
+        #(#persist_impls)*
+        #columns_struct
+        #default_impl
+        #(#supports_impls)*
+        #(#from_row_impls)*
+
+        #(#relation_assertions)*
     };
 
     TokenStream::from(expanded)
@@ -0,0 +1,105 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::{abort, emit_error};
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Attribute, DeriveInput, Fields};
+
+// `#[naorm_cfg(model = BookNote)]` on the struct
+fn parse_model(ident: &syn::Ident, attrs: &[Attribute]) -> syn::Type {
+    let mut model = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("naorm_cfg")) {
+        let res = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("model") {
+                model = Some(meta.value()?.parse::<syn::Type>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown naorm_cfg option of a projection, expected `model = ...`"))
+            }
+        });
+        if let Err(e) = res {
+            emit_error!(e.span(), "{}", e);
+        }
+    }
+    model.unwrap_or_else(|| abort!(ident, "missing `#[naorm_cfg(model = ...)]`"))
+}
+
+/// Expansion of `#[derive(NaormProjection)]`: a `SELECT` of the fields of the struct
+/// from the table of the model, and the query helpers of the model restricted to it.
+pub fn derive(input: &DeriveInput, naorm: &TokenStream) -> TokenStream {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: Fields::Named(named),
+            ..
+        }) => named,
+        _ => abort!(ident, "NaormProjection can only be derived for structs with named fields"),
+    };
+    if !input.generics.params.is_empty() {
+        emit_error!(input.generics, "NaormProjection can not be derived for generic structs");
+    }
+    let model = parse_model(ident, &input.attrs);
+    let mut columns: Vec<String> = Vec::new();
+    let mut checks: Vec<TokenStream> = Vec::new();
+    let mut column_checks: Vec<TokenStream> = Vec::new();
+    for field in &fields.named {
+        let field_ident = field.ident.as_ref().unwrap();
+        if field.attrs.iter().any(|a| a.path().is_ident("naorm_cfg")) {
+            emit_error!(field_ident, "projection fields take no `naorm_cfg` options");
+        }
+        let ty = &field.ty;
+        columns.push(field_ident.to_string());
+        // a missing field or another type fails here, at the field of the projection
+        checks.push(quote_spanned! { ty.span() =>
+            #naorm::projection::compatible::<#ty, _>(&model.#field_ident);
+        });
+        // `flatten` fields of the model pass the check above but have no column
+        let msg = format!(
+            "projection field `{}` is not a column of the model; `flatten` fields are stored in their prefixed columns",
+            field_ident
+        );
+        let name = field_ident.to_string();
+        column_checks.push(quote_spanned! { field_ident.span() =>
+            assert!(#naorm::projection::has_column(<#model as #naorm::Model>::COLUMNS, #name), #msg);
+        });
+    }
+    if columns.is_empty() {
+        abort!(ident, "a projection needs at least one field");
+    }
+    let select = format!("SELECT {} FROM ", columns.join(", "));
+    let db = quote! { <#model as #naorm::Model>::Database };
+    let row = quote! { <#db as sqlx::Database>::Row };
+    quote! {
+        const _: () = {
+            #[allow(dead_code)]
+            fn check(model: &#model) {
+                #(#checks)*
+            }
+            #(#column_checks)*
+        };
+
+        impl #ident {
+            pub const SELECT_SQL: &'static str = {
+                const PARTS: &[&str] = &[#select, <#model as #naorm::Model>::TABLE];
+                const BYTES: [u8; #naorm::__private::concat_len(PARTS)] = #naorm::__private::concat(PARTS);
+                #naorm::__private::utf8(&BYTES)
+            };
+            /// Column names in `SELECT_SQL` order.
+            pub const COLUMNS: &'static [&'static str] = &[#(#columns),*];
+
+            pub fn all_query() -> sqlx::query::QueryAs<'static, #db, Self, <#db as sqlx::Database>::Arguments<'static>>
+            where
+                Self: for<'r> sqlx::FromRow<'r, #row>,
+            {
+                sqlx::query_as::<#db, Self>(Self::SELECT_SQL)
+            }
+            pub fn filter_query<'q>(
+                w: &'q str,
+            ) -> sqlx::query::QueryAs<'q, #db, Self, <#db as sqlx::Database>::Arguments<'q>>
+            where
+                Self: for<'r> sqlx::FromRow<'r, #row>,
+            {
+                sqlx::query_as::<#db, Self>(w)
+            }
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::attrs::{HasRelation, ManyToMany};
+use crate::driver::Driver;
+use crate::table_create::{self, Column};
+
+/// A `#[naorm_cfg(belongs_to = "Target")]` foreign key field.
+pub struct BelongsTo {
+    field: syn::Ident,
+    field_type: syn::Ident,
+    is_option: bool,
+    target: syn::Path,
+    // referenced table/column, `None` when taken from the target's NAORM_TABLE/PK
+    references: Option<(String, String)>,
+    table: String,
+    column: String,
+    on_delete: Option<String>,
+    on_update: Option<String>,
+    name: String,
+}
+
+impl BelongsTo {
+    pub fn from_column(col: &Column) -> Option<BelongsTo> {
+        let target = col.attrs.belongs_to.clone()?;
+        let target_name = target.segments.last().unwrap().ident.to_string();
+        let (table, column) = col
+            .attrs
+            .references
+            .clone()
+            .unwrap_or_else(|| (table_create::to_snake_case(&target_name), "id".to_string()));
+        // `book_id` -> `book`, otherwise named after the target type
+        let name = match col.name.strip_suffix("_id") {
+            Some(stem) if !stem.is_empty() => stem.to_string(),
+            _ => table_create::to_snake_case(&target_name),
+        };
+        Some(BelongsTo {
+            field: format_ident!("{}", col.name),
+            field_type: format_ident!("as_{}", table_create::to_snake_case(&col.ty_name)),
+            is_option: col.is_option,
+            target,
+            references: col.attrs.references.clone(),
+            table,
+            column,
+            on_delete: col.attrs.on_delete.clone(),
+            on_update: col.attrs.on_update.clone(),
+            name,
+        })
+    }
+
+    /// Referenced table, checked against the target by `assertions`.
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Table constraint for the generated `CREATE TABLE`.
+    pub fn foreign_key_sql(&self) -> String {
+        let mut sql = format!(
+            "FOREIGN KEY ({}) REFERENCES {} ({})",
+            self.field, self.table, self.column
+        );
+        if let Some(action) = &self.on_delete {
+            sql.push_str(&format!(" ON DELETE {}", action));
+        }
+        if let Some(action) = &self.on_update {
+            sql.push_str(&format!(" ON UPDATE {}", action));
+        }
+        sql
+    }
+
+    pub fn methods(&self, driver: Driver) -> TokenStream {
+        let target = &self.target;
+        let field = &self.field;
+        let db = driver.db();
+        let args = driver.arguments(quote! { 'q });
+        let row = driver.row();
+        let query_fn = format_ident!("{}_query", self.name);
+        let load_fn = format_ident!("load_{}", self.name);
+        let where_sql = format!(" WHERE {{}} = {}", driver.placeholder(1));
+        let column = match &self.references {
+            Some((_, column)) => quote! { #column },
+            None => quote! { <#target>::PK },
+        };
+        let bind = crate::bind_token(field, &self.field_type, self.is_option);
+        let load = if self.is_option {
+            quote! {
+                pub async fn #load_fn<'e, E>(&self, executor: E) -> Result<Option<#target>, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                    #target: for<'r> sqlx::FromRow<'r, #row> + Send + Unpin,
+                {
+                    if self.#field.is_none() {
+                        return Ok(None);
+                    }
+                    self.#query_fn().fetch_optional(executor).await
+                }
+            }
+        } else {
+            quote! {
+                pub async fn #load_fn<'e, E>(&self, executor: E) -> Result<#target, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                    #target: for<'r> sqlx::FromRow<'r, #row> + Send + Unpin,
+                {
+                    self.#query_fn().fetch_one(executor).await
+                }
+            }
+        };
+        quote! {
+            pub fn #query_fn<'q>(&'q self) -> sqlx::query::QueryAs<'q, #db, #target, #args>
+            where
+                #target: for<'r> sqlx::FromRow<'r, #row>,
+            {
+                static SQL: ::std::sync::OnceLock<String> = ::std::sync::OnceLock::new();
+                let sql = SQL.get_or_init(|| {
+                    format!(concat!("{}", #where_sql), <#target>::SELECT_SQL, #column)
+                });
+                sqlx::query_as::<#db, #target>(sql)
+                    #bind
+            }
+
+            #load
+        }
+    }
+
+    /// The DDL guesses the target table from its type name; fail the build when the
+    /// target says otherwise instead of emitting a dangling REFERENCES clause.
+    pub fn assertions(&self, naorm: &TokenStream) -> TokenStream {
+        if self.references.is_some() {
+            return TokenStream::new();
+        }
+        let target = &self.target;
+        let table = &self.table;
+        let column = &self.column;
+        let msg_table = format!(
+            "belongs_to: table of `{}` is not `{}`, set `references = \"table(column)\"`",
+            quote!(#target).to_string().replace(' ', ""),
+            table
+        );
+        let msg_pk = format!(
+            "belongs_to: primary key of `{}` is not `{}`, set `references = \"table(column)\"`",
+            quote!(#target).to_string().replace(' ', ""),
+            column
+        );
+        quote! {
+            const _: () = {
+                assert!(#naorm::__private::str_eq(<#target>::NAORM_TABLE, #table), #msg_table);
+                assert!(#naorm::__private::str_eq(<#target>::PK, #column), #msg_pk);
+            };
+        }
+    }
+}
+
+fn pluralize(s: &str) -> String {
+    if s.ends_with('s') || s.ends_with('x') || s.ends_with("ch") || s.ends_with("sh") {
+        format!("{}es", s)
+    } else if let Some(stem) = s.strip_suffix('y')
+        && !stem.ends_with(['a', 'e', 'i', 'o', 'u'])
+    {
+        format!("{}ies", stem)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Method stem of a has_many/has_one relation: `BookNote` under `Book` becomes
+/// `notes` (or `note` for has_one) unless `name = "..."` is given.
+fn has_name(rel: &HasRelation, parent: &str) -> String {
+    if let Some(name) = &rel.name {
+        return name.clone();
+    }
+    let child = table_create::to_snake_case(&rel.target.segments.last().unwrap().ident.to_string());
+    let parent = table_create::to_snake_case(parent);
+    let stem = match child.strip_prefix(&format!("{}_", parent)) {
+        Some(rest) if !rest.is_empty() => rest.to_string(),
+        _ => child,
+    };
+    if rel.many { pluralize(&stem) } else { stem }
+}
+
+/// Methods of a struct-level `has_many`/`has_one` declaration. The batched
+/// `load_*_for` issues one `WHERE fk IN (...)` query per `naorm::sql::IN_LIST_CHUNK`
+/// parents, below the bind limits of every backend, and groups the children by the
+/// foreign key read back from each row.
+pub fn has_methods(
+    rel: &HasRelation,
+    parent: &str,
+    driver: Driver,
+    naorm: &TokenStream,
+    pk_field: &syn::Field,
+    pk_col: &Column,
+) -> TokenStream {
+    let target = &rel.target;
+    let db = driver.db();
+    let args = driver.arguments(quote! { 'q });
+    let row = driver.row();
+    let style = driver.placeholder_style(naorm);
+    let name = has_name(rel, parent);
+    let query_fn = format_ident!("{}_query", name);
+    let load_fn = format_ident!("load_{}", name);
+    let load_for_fn = format_ident!("load_{}_for", name);
+    let fk = &rel.foreign_key;
+    let where_sql = format!(" WHERE {} = {}", fk, driver.placeholder(1));
+    let pk_ident = pk_field.ident.as_ref().unwrap();
+    let pk_ty = &pk_field.ty;
+    let pk_field_type = format_ident!("as_{}", table_create::to_snake_case(&pk_col.ty_name));
+    let self_bind = crate::bind_token(pk_ident, &pk_field_type, pk_col.is_option);
+    let parent_bind = crate::bind_value(&quote! { parent.#pk_ident }, &pk_field_type, pk_col.is_option);
+
+    let (load_ret, load_body, grouped_ty, group) = if rel.many {
+        (
+            quote! { Vec<#target> },
+            quote! { self.#query_fn().fetch_all(executor).await },
+            quote! { ::std::collections::HashMap<#pk_ty, Vec<#target>> },
+            quote! { out.entry(key).or_default().push(child); },
+        )
+    } else {
+        (
+            quote! { Option<#target> },
+            quote! { self.#query_fn().fetch_optional(executor).await },
+            quote! { ::std::collections::HashMap<#pk_ty, #target> },
+            quote! { out.insert(key, child); },
+        )
+    };
+
+    quote! {
+        pub fn #query_fn<'q>(&'q self) -> sqlx::query::QueryAs<'q, #db, #target, #args>
+        where
+            #target: for<'r> sqlx::FromRow<'r, #row>,
+        {
+            static SQL: ::std::sync::OnceLock<String> = ::std::sync::OnceLock::new();
+            let sql = SQL.get_or_init(|| format!(concat!("{}", #where_sql), <#target>::SELECT_SQL));
+            sqlx::query_as::<#db, #target>(sql)
+                #self_bind
+        }
+
+        pub async fn #load_fn<'e, E>(&self, executor: E) -> Result<#load_ret, sqlx::Error>
+        where
+            E: sqlx::Executor<'e, Database = #db>,
+            #target: for<'r> sqlx::FromRow<'r, #row> + Send + Unpin,
+        {
+            #load_body
+        }
+
+        /// Loads the related rows of all `parents`, keyed by parent primary key, with one
+        /// query per `naorm::sql::IN_LIST_CHUNK` parents on the same connection.
+        pub async fn #load_for_fn<'c, A>(parents: &[Self], conn: A) -> Result<#grouped_ty, sqlx::Error>
+        where
+            A: sqlx::Acquire<'c, Database = #db>,
+            #target: for<'r> sqlx::FromRow<'r, #row>,
+        {
+            let mut out: #grouped_ty = ::std::collections::HashMap::new();
+            if parents.is_empty() {
+                return Ok(out);
+            }
+            let mut conn = conn.acquire().await?;
+            for chunk in parents.chunks(#naorm::sql::IN_LIST_CHUNK) {
+                let sql = format!(
+                    "{} WHERE {} IN ({})",
+                    <#target>::SELECT_SQL,
+                    #fk,
+                    #style.list(1, chunk.len())
+                );
+                let mut query = sqlx::query::<#db>(&sql);
+                for parent in chunk {
+                    query = query #parent_bind;
+                }
+                for row in query.fetch_all(&mut *conn).await? {
+                    let key: #pk_ty = sqlx::Row::try_get(&row, #fk)?;
+                    let child = <#target as sqlx::FromRow<'_, #row>>::from_row(&row)?;
+                    #group
+                }
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Methods of a struct-level `many_to_many` declaration. Links are rows of the
+/// `through` table written with the same INSERT/DELETE builders as the struct itself;
+/// the target's key comes from its `naorm::Model` impl.
+pub fn many_to_many_methods(
+    rel: &ManyToMany,
+    driver: Driver,
+    naorm: &TokenStream,
+    pk_field: &syn::Field,
+    pk_col: &Column,
+) -> TokenStream {
+    let target = &rel.target;
+    let db = driver.db();
+    let args = driver.arguments(quote! { 'q });
+    let row = driver.row();
+    let singular = table_create::to_snake_case(&target.segments.last().unwrap().ident.to_string());
+    let plural = rel.name.clone().unwrap_or_else(|| pluralize(&singular));
+    let query_fn = format_ident!("{}_query", plural);
+    let load_fn = format_ident!("load_{}", plural);
+    let sync_fn = format_ident!("sync_{}", plural);
+    let attach_fn = format_ident!("attach_{}", singular);
+    let detach_fn = format_ident!("detach_{}", singular);
+    let (through, local, foreign) = (&rel.through, &rel.local, &rel.foreign);
+
+    let where_sql = format!(
+        " WHERE {{}} IN (SELECT {} FROM {} WHERE {} = {})",
+        foreign,
+        through,
+        local,
+        driver.placeholder(1)
+    );
+    let link_cols = [local.clone(), foreign.clone()];
+    let attach_sql = table_create::insert_sql(driver, through, &link_cols);
+    let detach_sql = table_create::delete_sql(driver, through, &link_cols);
+    let linked_sql = format!(
+        "SELECT {} FROM {} WHERE {} = {}",
+        foreign,
+        through,
+        local,
+        driver.placeholder(1)
+    );
+    let pk_ident = pk_field.ident.as_ref().unwrap();
+    let pk_field_type = format_ident!("as_{}", table_create::to_snake_case(&pk_col.ty_name));
+    let self_bind = crate::bind_token(pk_ident, &pk_field_type, pk_col.is_option);
+    let key = quote! { <#target as #naorm::Model>::Key };
+    let key_bounds = quote! {
+        #key: for<'q> sqlx::Encode<'q, #db> + sqlx::Type<#db>
+    };
+
+    quote! {
+        pub fn #query_fn<'q>(&'q self) -> sqlx::query::QueryAs<'q, #db, #target, #args>
+        where
+            #target: for<'r> sqlx::FromRow<'r, #row>,
+        {
+            static SQL: ::std::sync::OnceLock<String> = ::std::sync::OnceLock::new();
+            let sql = SQL.get_or_init(|| {
+                format!(concat!("{}", #where_sql), <#target>::SELECT_SQL, <#target>::PK)
+            });
+            sqlx::query_as::<#db, #target>(sql)
+                #self_bind
+        }
+
+        pub async fn #load_fn<'e, E>(&self, executor: E) -> Result<Vec<#target>, sqlx::Error>
+        where
+            E: sqlx::Executor<'e, Database = #db>,
+            #target: for<'r> sqlx::FromRow<'r, #row> + Send + Unpin,
+        {
+            self.#query_fn().fetch_all(executor).await
+        }
+
+        pub async fn #attach_fn<'e, E>(&self, target: &#target, executor: E) -> Result<(), sqlx::Error>
+        where
+            E: sqlx::Executor<'e, Database = #db>,
+            #key_bounds,
+        {
+            sqlx::query(#attach_sql)
+                #self_bind
+                .bind(#naorm::Model::key(target))
+                .execute(executor)
+                .await?;
+            Ok(())
+        }
+
+        pub async fn #detach_fn<'e, E>(&self, target: &#target, executor: E) -> Result<(), sqlx::Error>
+        where
+            E: sqlx::Executor<'e, Database = #db>,
+            #key_bounds,
+        {
+            sqlx::query(#detach_sql)
+                #self_bind
+                .bind(#naorm::Model::key(target))
+                .execute(executor)
+                .await?;
+            Ok(())
+        }
+
+        /// Makes `targets` the exact set of linked rows, in one transaction.
+        pub async fn #sync_fn<'a, A>(&self, targets: &[#target], conn: A) -> Result<(), sqlx::Error>
+        where
+            A: sqlx::Acquire<'a, Database = #db>,
+            #key_bounds + for<'r> sqlx::Decode<'r, #db> + PartialEq + Send + Unpin,
+        {
+            let mut tx = conn.begin().await?;
+            let linked: Vec<#key> = sqlx::query_scalar::<#db, #key>(#linked_sql)
+                #self_bind
+                .fetch_all(&mut *tx)
+                .await?;
+            for key in &linked {
+                if !targets.iter().any(|t| #naorm::Model::key(t) == key) {
+                    sqlx::query(#detach_sql)
+                        #self_bind
+                        .bind(key)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+            let mut attached: Vec<&#key> = linked
+                .iter()
+                .filter(|key| targets.iter().any(|t| #naorm::Model::key(t) == *key))
+                .collect();
+            for target in targets {
+                let key = #naorm::Model::key(target);
+                if !attached.contains(&key) {
+                    sqlx::query(#attach_sql)
+                        #self_bind
+                        .bind(key)
+                        .execute(&mut *tx)
+                        .await?;
+                    attached.push(key);
+                }
+            }
+            tx.commit().await
+        }
+    }
+}
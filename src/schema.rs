@@ -0,0 +1,170 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::driver::{Driver, Target};
+use crate::table_create::{self, Column, Index};
+
+/// Body of the generated `Model::schema()`; the description of the primary driver
+/// (see `Target::primary`) when SQL is generated per dialect.
+pub fn schema_tokens(naorm: &TokenStream, target: Target, columns: &[Column], indexes: &[Index]) -> TokenStream {
+    match target {
+        Target::One(driver) => table_schema(naorm, driver, columns, indexes, quote! { Self::CREATE_TABLE_SQL }),
+        _ => {
+            let backend = target.primary().backend(naorm);
+            quote! { Self::schema_for(#backend) }
+        }
+    }
+}
+
+/// `schema_for(backend)` of structs with SQL per dialect.
+pub fn schema_for_tokens(naorm: &TokenStream, target: Target, columns: &[Column], indexes: &[Index]) -> TokenStream {
+    if let Target::One(_) = target {
+        return TokenStream::new();
+    }
+    let arms = [Driver::Sqlite, Driver::MySql, Driver::Postgres].map(|driver| {
+        let backend = driver.backend(naorm);
+        let schema = table_schema(naorm, driver, columns, indexes, quote! { Self::CREATE_TABLE_SQL.get(#backend) });
+        quote! { #backend => #schema }
+    });
+    quote! {
+        /// Table description in the dialect of `backend`.
+        pub fn schema_for(backend: #naorm::Backend) -> #naorm::schema::TableSchema {
+            match backend {
+                #(#arms,)*
+            }
+        }
+    }
+}
+
+fn table_schema(
+    naorm: &TokenStream,
+    driver: Driver,
+    columns: &[Column],
+    indexes: &[Index],
+    create_sql: TokenStream,
+) -> TokenStream {
+    let backend = driver.backend(naorm);
+    let column_tokens = columns.iter().map(|col| {
+        if col.attrs.flatten {
+            let ty = &col.ty.inner;
+            let prefix = col.attrs.prefix.clone().unwrap_or_default();
+            return quote! { columns.extend(#naorm::group::schema_columns::<#ty>(#backend, #prefix)); };
+        }
+        let name = &col.name;
+        let sql_type = table_create::const_str(naorm, driver, &table_create::ddl_type(driver, col), columns);
+        let nullable = col.is_option && !col.attrs.primary_key;
+        let primary_key = col.attrs.primary_key || col.attrs.auto_increment;
+        let auto_increment = col.attrs.auto_increment;
+        let default = match table_create::default_sql(col) {
+            Some(d) => quote! { Some(#d.to_string()) },
+            None => quote! { None },
+        };
+        let definition = table_create::const_str(naorm, driver, &table_create::column_def(driver, col), columns);
+        let renamed_from = match &col.attrs.renamed_from {
+            Some(r) => quote! { Some(#r.to_string()) },
+            None => quote! { None },
+        };
+        quote! {
+            columns.push(#naorm::schema::ColumnSchema {
+                name: #name.to_string(),
+                sql_type: #sql_type.to_string(),
+                nullable: #nullable,
+                primary_key: #primary_key,
+                auto_increment: #auto_increment,
+                default: #default,
+                definition: #definition.to_string(),
+                renamed_from: #renamed_from,
+            });
+        }
+    });
+    let index_tokens = indexes.iter().map(|index| {
+        let (name, columns, unique) = (&index.name, &index.columns, index.unique);
+        quote! {
+            #naorm::schema::IndexSchema {
+                name: #name.to_string(),
+                columns: vec![#(#columns.to_string()),*],
+                unique: #unique,
+            }
+        }
+    });
+    quote! {
+        #naorm::schema::TableSchema {
+            name: Self::NAORM_TABLE.to_string(),
+            backend: #backend,
+            create_sql: #create_sql.to_string(),
+            columns: {
+                let mut columns = Vec::new();
+                #(#column_tokens)*
+                columns
+            },
+            indexes: vec![#(#index_tokens),*],
+        }
+    }
+}
+
+/// The generated `verify_schema(executor)`; `any` structs take anything that can
+/// hand out a connection, whose backend decides the query and expected schema.
+pub fn verify_schema_tokens(naorm: &TokenStream, target: Target) -> TokenStream {
+    let doc = quote! {
+        /// Compares the live table with the struct: missing columns, unknown
+        /// `NOT NULL` columns without default, type and nullability mismatches.
+    };
+    let fetch = |sql: TokenStream, executor: TokenStream| {
+        quote! {
+            let rows: Vec<(String, String, String, Option<String>)> =
+                sqlx::query_as(#naorm::schema::introspect_sql(#sql))
+                    .bind(Self::NAORM_TABLE)
+                    .fetch_all(#executor)
+                    .await?;
+            let live: Vec<#naorm::schema::LiveColumn> = rows.into_iter().map(Into::into).collect();
+        }
+    };
+    match target {
+        Target::One(driver) => {
+            let db = driver.db();
+            let fetch = fetch(driver.backend(naorm), quote! { executor });
+            quote! {
+                #doc
+                pub async fn verify_schema<'e, E>(executor: E) -> Result<#naorm::schema::SchemaReport, sqlx::Error>
+                where
+                    E: sqlx::Executor<'e, Database = #db>,
+                    Self: #naorm::Model,
+                {
+                    #fetch
+                    Ok(#naorm::schema::SchemaReport::compare(
+                        &<Self as #naorm::Model>::schema(),
+                        &live,
+                    ))
+                }
+            }
+        }
+        Target::Any => {
+            let fetch = fetch(quote! { backend }, quote! { &mut *conn });
+            quote! {
+                #doc
+                pub async fn verify_schema<'c, A>(conn: A) -> Result<#naorm::schema::SchemaReport, sqlx::Error>
+                where
+                    A: sqlx::Acquire<'c, Database = sqlx::Any>,
+                {
+                    let mut conn = conn.acquire().await?;
+                    let backend = #naorm::Backend::of_any(&conn)?;
+                    #fetch
+                    Ok(#naorm::schema::SchemaReport::compare(&Self::schema_for(backend), &live))
+                }
+            }
+        }
+        Target::Many(_) => quote! {
+            #doc
+            pub async fn verify_schema<'e, E>(executor: E) -> Result<#naorm::schema::SchemaReport, sqlx::Error>
+            where
+                E: sqlx::Executor<'e> + 'e,
+                E::Database: #naorm::NaormDialect,
+                Self: #naorm::dialect::Supports<E::Database>,
+            {
+                let backend = <E::Database as #naorm::NaormDialect>::BACKEND;
+                let live = <E::Database as #naorm::NaormDialect>::live_columns(executor, Self::NAORM_TABLE).await?;
+                Ok(#naorm::schema::SchemaReport::compare(&Self::schema_for(backend), &live))
+            }
+        },
+    }
+}
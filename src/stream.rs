@@ -0,0 +1,124 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::driver::Target;
+use crate::table_create::Column;
+
+/// `stream_all`, `stream_filter`, `stream_chunks` and, with a primary key and a
+/// concrete driver, `stream_keyset` of the generated impl.
+pub fn methods(naorm: &TokenStream, target: Target, pk_ty: &syn::Type, pk: Option<&Column>) -> TokenStream {
+    let stream = quote! { #naorm::stream::Stream };
+    // `Database = ...` bound on the executor, and the bounds of the database
+    let (db, executor, dialect) = match target {
+        Target::Many(_) => (
+            quote! { E::Database },
+            quote! { sqlx::Executor<'e> },
+            quote! {
+                E::Database: #naorm::NaormDialect,
+                Self: #naorm::dialect::Supports<E::Database>,
+                for<'q> <E::Database as sqlx::Database>::Arguments<'q>: sqlx::IntoArguments<'q, E::Database>,
+            },
+        ),
+        _ => {
+            let db = target.db();
+            (db.clone(), quote! { sqlx::Executor<'e, Database = #db> }, quote! {})
+        }
+    };
+    let row_bounds = quote! {
+        #dialect
+        Self: for<'r> sqlx::FromRow<'r, <#db as sqlx::Database>::Row> + Send + Unpin + 'e,
+    };
+    let query_as = quote! { sqlx::query::QueryAs<'e, #db, Self, <#db as sqlx::Database>::Arguments<'e>> };
+    let keyset = pk
+        .filter(|_| !target.is_any())
+        .and_then(|col| keyset_method(naorm, target, pk_ty, col, &db, &executor, &row_bounds));
+    quote! {
+        /// Streams every row, decoded as the cursor advances.
+        pub fn stream_all<'e, E>(executor: E) -> impl #stream<Item = Result<Self, sqlx::Error>> + Send + 'e
+        where
+            E: #executor + 'e,
+            #row_bounds
+        {
+            sqlx::query_as::<#db, Self>(Self::SELECT_SQL).fetch(executor)
+        }
+        /// Streams the rows of the query `w` with the values `bind` adds to it, e.g.
+        /// `stream_filter(&sql, |q| q.bind(book_id), &pool)`.
+        pub fn stream_filter<'e, E, F>(w: &'e str, bind: F, executor: E) -> impl #stream<Item = Result<Self, sqlx::Error>> + Send + 'e
+        where
+            E: #executor + 'e,
+            F: FnOnce(#query_as) -> #query_as,
+            #row_bounds
+        {
+            bind(sqlx::query_as::<#db, Self>(w)).fetch(executor)
+        }
+        /// `stream_all` in batches of up to `size` rows.
+        pub fn stream_chunks<'e, E>(executor: E, size: usize) -> impl #stream<Item = Result<Vec<Self>, sqlx::Error>> + Send + 'e
+        where
+            E: #executor + 'e,
+            #row_bounds
+        {
+            #naorm::stream::chunks(Self::stream_all(executor), size)
+        }
+        #keyset
+    }
+}
+
+fn keyset_method(
+    naorm: &TokenStream,
+    target: Target,
+    pk_ty: &syn::Type,
+    col: &Column,
+    db: &TokenStream,
+    executor: &TokenStream,
+    row_bounds: &TokenStream,
+) -> Option<TokenStream> {
+    // keys read through `bind_with` have no type to bind here
+    if col.attrs.bind_with.is_some() || (col.attrs.transparent && col.attrs.wrapped.is_none()) {
+        return None;
+    }
+    let field = format_ident!("{}", col.name);
+    let (key_ty, key) = match &col.attrs.wrapped {
+        Some(wrapped) => (quote! { #wrapped }, quote! { row.#field.0.clone() }),
+        None => (quote! { #pk_ty }, quote! { row.#field.clone() }),
+    };
+    let pk = &col.name;
+    let suffixes = |next: bool| {
+        target.sql(naorm, |d| {
+            let sql = if next {
+                format!(" WHERE {pk} > {} ORDER BY {pk} LIMIT {}", d.placeholder(1), d.placeholder(2))
+            } else {
+                format!(" ORDER BY {pk} LIMIT {}", d.placeholder(1))
+            };
+            quote! { #sql }
+        })
+    };
+    let (first, next) = match target {
+        Target::Many(_) => {
+            let backend = quote! { <#db as #naorm::NaormDialect>::BACKEND };
+            let (first, next) = (suffixes(false), suffixes(true));
+            (quote! { #first.get(#backend) }, quote! { #next.get(#backend) })
+        }
+        _ => (suffixes(false), suffixes(true)),
+    };
+    Some(quote! {
+        /// Batches of up to `batch_size` rows in primary key order, each read by its
+        /// own query after the last key seen, so long exports hold no cursor open.
+        /// Takes a pool (or another `Copy` executor).
+        pub fn stream_keyset<'e, E>(executor: E, batch_size: usize) -> impl #naorm::stream::Stream<Item = Result<Vec<Self>, sqlx::Error>> + 'e
+        where
+            E: #executor + Copy + 'e,
+            #row_bounds
+            #key_ty: for<'q> sqlx::Encode<'q, #db> + sqlx::Type<#db> + Send + 'e,
+            i64: for<'q> sqlx::Encode<'q, #db> + sqlx::Type<#db>,
+            for<'q> <#db as sqlx::Database>::Arguments<'q>: sqlx::IntoArguments<'q, #db>,
+        {
+            #naorm::stream::keyset(
+                executor,
+                format!("{}{}", Self::SELECT_SQL, #first),
+                format!("{}{}", Self::SELECT_SQL, #next),
+                batch_size,
+                |row: &Self| -> #key_ty { #key },
+            )
+        }
+    })
+}
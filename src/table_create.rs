@@ -1,3 +1,39 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+use crate::attrs::{self, DefaultValue, FieldAttrs};
+use crate::driver::Driver;
+use crate::types::{FieldType, LibraryType};
+
+/// Delimits placeholders for constants of `#[naorm_cfg(enum)]` column types and the
+/// columns of `flatten` fields in generated SQL, resolved by [`const_str`].
+const MARKER: char = '\u{1}';
+
+/// Marks where the prefix goes in the column definitions of a `NaormColumns` type,
+/// `naorm::group::PREFIX_MARKER`.
+pub const PREFIX_MARKER: char = '\u{2}';
+
+fn marker(kind: &str, column: &str) -> String {
+    format!("{}{}:{}{}", MARKER, kind, column, MARKER)
+}
+
+/// Stands for the column names of a `flatten` field in field lists.
+pub fn group_names(column: &str) -> String {
+    marker("names", column)
+}
+
+// the `flatten` field of a `group_names` entry
+fn group_of(field: &str) -> Option<&str> {
+    field
+        .strip_prefix(MARKER)?
+        .strip_suffix(MARKER)?
+        .strip_prefix("names:")
+}
+
+// kinds of the markers of `flatten` fields
+const GROUP_KINDS: [&str; 4] = ["names", "params", "assign", "defs"];
+
 pub fn to_snake_case(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for (i, ch) in s.chars().enumerate() {
@@ -15,37 +51,478 @@ pub fn to_snake_case(s: &str) -> String {
     out
 }
 
-// pub fn CreateSqlite(
-//     table_name: &str,
-//     fields: Vec<(String, syn::Ident, bool, bool, bool, String)>,
-// ) -> String {
-//     let mut sql = format!("CREATE TABLE IF NOT EXISTS {} (", table_name);
-//     let mut field_defs: Vec<String> = Vec::new();
-//     for (name, ty, is_option, is_auto_inc, is_pk, default) in fields.iter() {
-//         let type_name = match ty.to_string().as_str() {
-//             "String" => "TEXT",
-//             "u64" | "u32" | "i64" | "i32" | "usize" | "isize" => "INTEGER",
-//             "f64" | "f32" => "REAL",
-//             "bool" => "BOOLEAN",
-//             _ => "BLOB", // Default to BLOB for unsupported types
-//         };
-//         let mut field_def = format!("{} {}", to_snake_case(name.as_str()), type_name);
-
-//         if *is_pk {
-//             field_def.push_str(" PRIMARY KEY");
-//         }
-//         if *is_auto_inc {
-//             field_def.push_str(" AUTOINCREMENT");
-//         }
-//         if !*is_option {
-//             field_def.push_str(" NOT NULL");
-//         }
-//         if !default.is_empty() {
-//             field_def.push_str(&format!(" DEFAULT {}", default));
-//         }
-//         field_defs.push(field_def);
-//     }
-//     sql.push_str(field_defs.join(",\n ").as_str());
-//     sql.push(')');
-//     sql
-// }
+pub fn insert_sql(driver: Driver, table_name: &str, fields: &[String]) -> String {
+    if fields.is_empty() {
+        return format!("INSERT INTO {} DEFAULT VALUES", table_name);
+    }
+    let placeholders: Vec<String> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match group_of(field) {
+            Some(group) => marker("params", group),
+            None => driver.placeholder(i + 1),
+        })
+        .collect();
+    format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table_name,
+        fields.join(", "),
+        placeholders.join(", ")
+    )
+}
+
+/// `UPDATE ... SET a = ?, b = ? WHERE pk = ?`, the primary key is bound last.
+pub fn update_sql(driver: Driver, table_name: &str, fields: &[String], pk: &str) -> String {
+    let set_clauses: Vec<String> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match group_of(field) {
+            Some(group) => marker("assign", group),
+            None => format!("{} = {}", field, driver.placeholder(i + 1)),
+        })
+        .collect();
+    format!(
+        "UPDATE {} SET {} WHERE {} = {}",
+        table_name,
+        set_clauses.join(", "),
+        pk,
+        driver.placeholder(fields.len() + 1)
+    )
+}
+
+/// `DELETE FROM ... WHERE a = ? AND b = ?`
+pub fn delete_sql(driver: Driver, table_name: &str, where_fields: &[String]) -> String {
+    let conds: Vec<String> = where_fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| format!("{} = {}", field, driver.placeholder(i + 1)))
+        .collect();
+    format!("DELETE FROM {} WHERE {}", table_name, conds.join(" AND "))
+}
+
+/// Column description used to build the `CREATE TABLE` statement.
+pub struct Column {
+    pub name: String,
+    pub ty_name: String,
+    pub is_option: bool,
+    pub attrs: FieldAttrs,
+    /// Part of an index, mysql needs a bounded type for it.
+    pub indexed: bool,
+    /// The `NaormEnum` type of an `enum` column, `Option` removed.
+    pub enum_ty: Option<syn::Type>,
+    pub ty: FieldType,
+}
+
+impl Column {
+    pub fn is_text(&self) -> bool {
+        matches!(self.ty_name.as_str(), "String" | "str")
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self.ty_name.as_str(),
+            "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize"
+        )
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self.ty_name.as_str(), "f32" | "f64")
+    }
+}
+
+pub fn sql_type(driver: Driver, col: &Column) -> String {
+    let ty = col.ty_name.as_str();
+    if let Some(ty) = col.attrs.sql_type.get(driver) {
+        return ty.to_string();
+    }
+    if col.enum_ty.is_some() {
+        return marker("type", &col.name);
+    }
+    if let Some(lib) = col.ty.library_type() {
+        return library_sql_type(driver, lib).to_string();
+    }
+    if col.ty.bytes {
+        let name = match driver {
+            Driver::Postgres => "BYTEA",
+            _ => "BLOB",
+        };
+        return name.to_string();
+    }
+    if col.attrs.json {
+        let name = match driver {
+            Driver::Sqlite => "TEXT",
+            Driver::MySql => "JSON",
+            Driver::Postgres => "JSONB",
+        };
+        return name.to_string();
+    }
+    if col.is_text() {
+        return match (driver, col.attrs.max_len) {
+            (_, Some(n)) => format!("VARCHAR({})", n),
+            // mysql can not use TEXT as a key without a prefix length
+            (Driver::MySql, None) if col.attrs.primary_key || col.indexed => "VARCHAR(255)".to_string(),
+            _ => "TEXT".to_string(),
+        };
+    }
+    let name = match driver {
+        Driver::Sqlite => match ty {
+            "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+                "INTEGER"
+            }
+            "f32" | "f64" => "REAL",
+            "bool" => "BOOLEAN",
+            _ => "BLOB", // Default to BLOB for unsupported types
+        },
+        Driver::MySql => match ty {
+            "i8" => "TINYINT",
+            "i16" => "SMALLINT",
+            "i32" => "INT",
+            "i64" | "isize" => "BIGINT",
+            "u8" => "TINYINT UNSIGNED",
+            "u16" => "SMALLINT UNSIGNED",
+            "u32" => "INT UNSIGNED",
+            "u64" | "usize" => "BIGINT UNSIGNED",
+            "f32" => "FLOAT",
+            "f64" => "DOUBLE",
+            "bool" => "BOOLEAN",
+            _ => "BLOB",
+        },
+        Driver::Postgres => match ty {
+            "i8" | "i16" => "SMALLINT",
+            "i32" => "INTEGER",
+            "i64" | "isize" | "u32" | "u64" | "usize" => "BIGINT",
+            "f32" => "REAL",
+            "f64" => "DOUBLE PRECISION",
+            "bool" => "BOOLEAN",
+            _ => "BYTEA",
+        },
+    };
+    name.to_string()
+}
+
+fn library_sql_type(driver: Driver, ty: LibraryType) -> &'static str {
+    match (ty, driver) {
+        (LibraryType::DateTimeTz, Driver::Sqlite) => "DATETIME",
+        (LibraryType::DateTimeTz, Driver::MySql) => "TIMESTAMP",
+        (LibraryType::DateTimeTz, Driver::Postgres) => "TIMESTAMPTZ",
+        (LibraryType::DateTime, Driver::Postgres) => "TIMESTAMP",
+        (LibraryType::DateTime, _) => "DATETIME",
+        (LibraryType::Date, _) => "DATE",
+        (LibraryType::Time, _) => "TIME",
+        // sqlx encodes `Uuid` as 16 bytes on sqlite and mysql
+        (LibraryType::Uuid, Driver::Sqlite) => "BLOB",
+        (LibraryType::Uuid, Driver::MySql) => "BINARY(16)",
+        (LibraryType::UuidText, Driver::Sqlite) => "TEXT",
+        (LibraryType::UuidText, Driver::MySql) => "CHAR(36)",
+        (LibraryType::Uuid | LibraryType::UuidText, Driver::Postgres) => "UUID",
+        (LibraryType::Decimal, Driver::Sqlite) => "NUMERIC",
+        (LibraryType::Decimal, _) => "NUMERIC(28, 10)",
+        (LibraryType::Bytes, Driver::Postgres) => "BYTEA",
+        (LibraryType::Bytes, _) => "BLOB",
+    }
+}
+
+/// SQL type as written in the column definition, auto increment included.
+pub fn ddl_type(driver: Driver, col: &Column) -> String {
+    if !col.attrs.auto_increment || col.attrs.sql_type.get(driver).is_some() {
+        return sql_type(driver, col);
+    }
+    match driver {
+        Driver::Sqlite => "INTEGER".to_string(),
+        Driver::MySql => sql_type(driver, col),
+        Driver::Postgres => match col.ty_name.as_str() {
+            "i32" => "SERIAL".to_string(),
+            _ => "BIGSERIAL".to_string(),
+        },
+    }
+}
+
+fn auto_increment_def(driver: Driver, col: &Column) -> String {
+    let ty = ddl_type(driver, col);
+    match driver {
+        Driver::Sqlite => format!("{} {} PRIMARY KEY AUTOINCREMENT", col.name, ty),
+        Driver::MySql => format!("{} {} NOT NULL AUTO_INCREMENT PRIMARY KEY", col.name, ty),
+        Driver::Postgres => format!("{} {} PRIMARY KEY", col.name, ty),
+    }
+}
+
+/// The explicit default of a column as written after `DEFAULT`. Strings are quoted
+/// (except numbers given as strings for number columns), `default_sql` expressions
+/// are parenthesized unless they are a single word or literal.
+pub fn default_sql(col: &Column) -> Option<String> {
+    let sql = match col.attrs.default.as_ref()? {
+        DefaultValue::Str(lit) => {
+            let value = lit.value();
+            let plain = value.parse::<f64>().is_ok() || value == "true" || value == "false";
+            if plain && !col.is_text() && !col.attrs.json {
+                value
+            } else {
+                format!("'{}'", value.replace('\'', "''"))
+            }
+        }
+        DefaultValue::Number(n) => attrs::expr_sql(n),
+        DefaultValue::Bool(b) => b.to_string(),
+        DefaultValue::Sql(sql) => {
+            let word = sql.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+            let quoted = sql.len() > 1 && sql.starts_with('\'') && sql.ends_with('\'');
+            if word || quoted {
+                sql.clone()
+            } else {
+                // sqlite and mysql only take other expressions in parentheses
+                format!("({})", sql)
+            }
+        }
+    };
+    Some(sql)
+}
+
+/// CHECK expressions mirroring the validation rules of a column.
+fn checks(driver: Driver, col: &Column) -> Vec<String> {
+    let mut out = Vec::new();
+    if let Some(n) = col.attrs.max_len {
+        // only sqlite ignores the VARCHAR length
+        if driver == Driver::Sqlite && col.is_text() {
+            out.push(format!("length({}) <= {}", col.name, n));
+        }
+    }
+    if let Some(min) = &col.attrs.min {
+        out.push(format!("{} >= {}", col.name, attrs::expr_sql(min)));
+    }
+    if let Some(max) = &col.attrs.max {
+        out.push(format!("{} <= {}", col.name, attrs::expr_sql(max)));
+    }
+    if col.attrs.not_empty && col.is_text() {
+        out.push(format!("{} <> ''", col.name));
+    }
+    if col.enum_ty.is_some() {
+        out.push(format!("{} IN ({})", col.name, marker("values", &col.name)));
+    }
+    out
+}
+
+/// Full column definition as used in `CREATE TABLE` and `ALTER TABLE ... ADD COLUMN`.
+pub fn column_def(driver: Driver, col: &Column) -> String {
+    if col.attrs.flatten {
+        return marker("defs", &col.name);
+    }
+    let mut field_def = if col.attrs.auto_increment {
+        auto_increment_def(driver, col)
+    } else {
+        let mut def = format!("{} {}", col.name, sql_type(driver, col));
+        if col.attrs.primary_key {
+            def.push_str(" PRIMARY KEY");
+        } else if !col.is_option {
+            def.push_str(" NOT NULL");
+        }
+        def
+    };
+    if let Some(default) = default_sql(col) {
+        field_def.push_str(&format!(" DEFAULT {}", default));
+    }
+    let checks = checks(driver, col);
+    if !checks.is_empty() {
+        field_def.push_str(&format!(" CHECK ({})", checks.join(" AND ")));
+    }
+    field_def
+}
+
+/// `constraints` are appended as table constraints after the column definitions.
+pub fn create_table_sql(
+    driver: Driver,
+    table_name: &str,
+    columns: &[Column],
+    constraints: &[String],
+) -> String {
+    let mut field_defs: Vec<String> = columns.iter().map(|col| column_def(driver, col)).collect();
+    field_defs.extend(constraints.iter().cloned());
+    // native postgres enum types have to exist before the table
+    let create_types: String = match driver {
+        Driver::Postgres => columns
+            .iter()
+            .filter(|c| c.enum_ty.is_some())
+            .map(|c| marker("create_type", &c.name))
+            .collect(),
+        _ => String::new(),
+    };
+    format!(
+        "{}CREATE TABLE IF NOT EXISTS {} (\n    {}\n)",
+        create_types,
+        table_name,
+        field_defs.join(",\n    ")
+    )
+}
+
+/// An index created next to the table, from field-level `index`/`unique` or a
+/// struct-level `index(...)`.
+pub struct Index {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+impl Index {
+    pub fn new(table_name: &str, name: Option<String>, columns: Vec<String>, unique: bool) -> Index {
+        let name = name.unwrap_or_else(|| {
+            format!(
+                "{}_{}_{}",
+                table_name,
+                columns.join("_"),
+                if unique { "unique" } else { "index" }
+            )
+        });
+        Index { name, columns, unique }
+    }
+}
+
+/// `CREATE [UNIQUE] INDEX`, with `IF NOT EXISTS` where the driver supports it.
+pub fn create_index_sql(driver: Driver, table_name: &str, index: &Index) -> String {
+    format!(
+        "CREATE {}INDEX {}{} ON {} ({})",
+        if index.unique { "UNIQUE " } else { "" },
+        if driver == Driver::MySql { "" } else { "IF NOT EXISTS " },
+        index.name,
+        table_name,
+        index.columns.join(", ")
+    )
+}
+
+/// Script run by the generated `create_table`: the table, its indexes, then `extra`
+/// statements (full-text tables, triggers). MySQL has no `CREATE INDEX IF NOT EXISTS`,
+/// the statement is only prepared when `information_schema` does not know the index yet.
+pub fn create_all_sql(
+    driver: Driver,
+    table_name: &str,
+    create_table: &str,
+    indexes: &[Index],
+    extra: &[String],
+) -> String {
+    let mut out = vec![create_table.to_string()];
+    for index in indexes {
+        let create = create_index_sql(driver, table_name, index);
+        if driver == Driver::MySql {
+            out.extend(mysql_create_index_if_missing(table_name, &index.name, &create));
+        } else {
+            out.push(create);
+        }
+    }
+    out.extend(extra.iter().cloned());
+    out.join(";\n")
+}
+
+/// Runs the MySQL `create` statement only when `information_schema` does not know the
+/// index `name` of `table_name` yet.
+pub fn mysql_create_index_if_missing(table_name: &str, name: &str, create: &str) -> Vec<String> {
+    vec![
+        format!(
+            "SET @naorm_sql = IF((SELECT COUNT(*) FROM information_schema.STATISTICS \
+             WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = {} AND INDEX_NAME = {}) = 0, \
+             {}, 'DO 0')",
+            mysql_string(table_name),
+            mysql_string(name),
+            mysql_string(create)
+        ),
+        "PREPARE naorm_stmt FROM @naorm_sql".to_string(),
+        "EXECUTE naorm_stmt".to_string(),
+        "DEALLOCATE PREPARE naorm_stmt".to_string(),
+    ]
+}
+
+// `value` as a MySQL string literal, `\` and `'` escaped
+fn mysql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+/// A `&'static str` expression for generated `sql`. Placeholders of enum columns
+/// become a compile-time concatenation with the constants of their `NaormEnum` type.
+pub fn const_str(naorm: &TokenStream, driver: Driver, sql: &str, columns: &[Column]) -> TokenStream {
+    if !sql.contains(MARKER) {
+        let lit = LitStr::new(sql, proc_macro2::Span::call_site());
+        return quote! { #lit };
+    }
+    if GROUP_KINDS.iter().any(|kind| sql.contains(&format!("{}{}:", MARKER, kind))) {
+        return group_const_str(naorm, driver, sql, columns);
+    }
+    let parts = sql.split(MARKER).enumerate().map(|(i, part)| {
+        if i % 2 == 0 {
+            return quote! { #part };
+        }
+        let (kind, column) = part.split_once(':').unwrap_or_default();
+        let ty = columns
+            .iter()
+            .find(|c| c.name == column)
+            .and_then(|c| c.enum_ty.as_ref())
+            .expect("marker of an enum column");
+        let constant = match (kind, driver) {
+            ("values", _) => quote! { CHECK_VALUES },
+            ("create_type", _) => quote! { POSTGRES_CREATE_TYPE_SQL },
+            (_, Driver::Sqlite) => quote! { SQLITE_TYPE },
+            (_, Driver::MySql) => quote! { MYSQL_TYPE },
+            (_, Driver::Postgres) => quote! { POSTGRES_TYPE },
+        };
+        quote! { <#ty as #naorm::NaormEnum>::#constant }
+    });
+    quote! {
+        {
+            const PARTS: &[&str] = &[#(#parts),*];
+            const BYTES: [u8; #naorm::__private::concat_len(PARTS)] = #naorm::__private::concat(PARTS);
+            #naorm::__private::utf8(&BYTES)
+        }
+    }
+}
+
+/// [`const_str`] of SQL with the columns of `flatten` fields, rendered by
+/// `naorm::group::render`. Statements binding group columns number all their postgres
+/// placeholders there, as the group sizes are only known to the compiler.
+fn group_const_str(naorm: &TokenStream, driver: Driver, sql: &str, columns: &[Column]) -> TokenStream {
+    let renumber = driver == Driver::Postgres && (sql.contains("\u{1}params:") || sql.contains("\u{1}assign:"));
+    let backend = driver.backend(naorm);
+    let mut pieces = Vec::new();
+    for (i, part) in sql.split(MARKER).enumerate() {
+        if i % 2 == 0 {
+            if !renumber {
+                pieces.push(quote! { #naorm::group::Piece::Str(#part) });
+                continue;
+            }
+            // `$n` -> `Param`
+            let mut rest = part;
+            while let Some(at) = rest.find('$') {
+                let digits = rest[at + 1..].chars().take_while(char::is_ascii_digit).count();
+                let text = &rest[..at];
+                pieces.push(quote! { #naorm::group::Piece::Str(#text) });
+                if digits > 0 {
+                    pieces.push(quote! { #naorm::group::Piece::Param });
+                } else {
+                    pieces.push(quote! { #naorm::group::Piece::Str("$") });
+                }
+                rest = &rest[at + 1 + digits..];
+            }
+            pieces.push(quote! { #naorm::group::Piece::Str(#rest) });
+            continue;
+        }
+        let (kind, column) = part.split_once(':').unwrap_or_default();
+        let col = columns.iter().find(|c| c.name == column).expect("marker of a column");
+        let ty = col.enum_ty.as_ref().unwrap_or(&col.ty.inner);
+        let group = quote! { <#ty as #naorm::ColumnGroup>::COLUMNS };
+        let prefix = col.attrs.prefix.clone().unwrap_or_default();
+        let piece = match (kind, driver) {
+            ("names", _) => quote! { Names(#prefix, #group) },
+            ("params", _) => quote! { Params(#group) },
+            ("assign", _) => quote! { Assign(#prefix, #group) },
+            ("defs", _) => quote! { Definitions(#prefix, #group, #backend) },
+            ("values", _) => quote! { Str(<#ty as #naorm::NaormEnum>::CHECK_VALUES) },
+            ("create_type", _) => quote! { Str(<#ty as #naorm::NaormEnum>::POSTGRES_CREATE_TYPE_SQL) },
+            (_, Driver::Sqlite) => quote! { Str(<#ty as #naorm::NaormEnum>::SQLITE_TYPE) },
+            (_, Driver::MySql) => quote! { Str(<#ty as #naorm::NaormEnum>::MYSQL_TYPE) },
+            (_, Driver::Postgres) => quote! { Str(<#ty as #naorm::NaormEnum>::POSTGRES_TYPE) },
+        };
+        pieces.push(quote! { #naorm::group::Piece::#piece });
+    }
+    quote! {
+        {
+            const PIECES: &[#naorm::group::Piece] = &[#(#pieces),*];
+            const BYTES: [u8; #naorm::group::render_len(PIECES, #renumber)] =
+                #naorm::group::render(PIECES, #renumber);
+            #naorm::__private::utf8(&BYTES)
+        }
+    }
+}
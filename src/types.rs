@@ -0,0 +1,271 @@
+use quote::ToTokens;
+
+/// What the derive knows about the Rust type of a field.
+#[derive(Clone)]
+pub struct FieldType {
+    /// The type with every `Option` layer removed.
+    pub inner: syn::Type,
+    /// `Option` layers around `inner`, `Option<Option<T>>` is 2.
+    pub option_depth: usize,
+    /// `inner` as written, e.g. `chrono::DateTime<Utc>` or `[u8; 16]`.
+    pub full: String,
+    /// Path of `inner` without generics, e.g. `chrono::DateTime`.
+    pub path: String,
+    /// Last path segment (`DateTime`), `Array`/`Slice`/`Tuple` for other types. Used
+    /// for the DDL type and the `as_*` bind markers.
+    pub name: String,
+    /// `Vec<u8>`, `[u8]`, `[u8; N]` and references to them.
+    pub bytes: bool,
+    /// `[u8; N]` and `bytes::Bytes`: bound as a slice, decoded through `Vec<u8>`.
+    pub via_vec: bool,
+    /// `Cow<'a, T>`: the borrowed `T`, decoded as `T::Owned`.
+    pub cow: Option<syn::Type>,
+}
+
+/// Types of the crates sqlx integrates with, recognised when the matching cargo
+/// feature is enabled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LibraryType {
+    /// `chrono::DateTime<Tz>`, `time::OffsetDateTime`
+    DateTimeTz,
+    /// `chrono::NaiveDateTime`, `time::PrimitiveDateTime`
+    DateTime,
+    Date,
+    Time,
+    /// `uuid::Uuid`, stored as 16 bytes outside postgres
+    Uuid,
+    /// `uuid::fmt::Hyphenated`
+    UuidText,
+    /// `rust_decimal::Decimal`, `bigdecimal::BigDecimal`
+    Decimal,
+    /// `bytes::Bytes`
+    Bytes,
+}
+
+impl LibraryType {
+    /// Inferred default as listed in `NAORM_FIELDS`.
+    pub fn default_value(self) -> &'static str {
+        match self {
+            LibraryType::DateTimeTz => "1970-01-01T00:00:00Z",
+            LibraryType::DateTime => "1970-01-01 00:00:00",
+            LibraryType::Date => "1970-01-01",
+            LibraryType::Time => "00:00:00",
+            LibraryType::Uuid | LibraryType::UuidText => "00000000-0000-0000-0000-000000000000",
+            LibraryType::Decimal => "0",
+            LibraryType::Bytes => "",
+        }
+    }
+}
+
+fn type_string(ty: &syn::Type) -> String {
+    // `chrono :: DateTime < Utc >` -> `chrono::DateTime<Utc>`, keeping the spaces of
+    // `&'a str`, `[u8; 16]` and `(A, B)`
+    let s = ty.to_token_stream().to_string();
+    let chars: Vec<char> = s.chars().collect();
+    let word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(s.len());
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch == ' ' {
+            let prev = if i > 0 { chars[i - 1] } else { ' ' };
+            let next = chars.get(i + 1).copied().unwrap_or(' ');
+            if !(matches!(prev, ',' | ';') || (word(prev) && word(next))) {
+                continue;
+            }
+        }
+        out.push(ch);
+    }
+    out
+}
+
+fn is_u8(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(p) if p.qself.is_none() && p.path.is_ident("u8"))
+}
+
+/// First generic type argument of a path segment.
+fn first_type_arg(seg: &syn::PathSegment) -> Option<&syn::Type> {
+    match &seg.arguments {
+        syn::PathArguments::AngleBracketed(ab) => ab.args.iter().find_map(|a| match a {
+            syn::GenericArgument::Type(t) => Some(t),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+impl FieldType {
+    pub fn analyze(ty: &syn::Type) -> FieldType {
+        let mut inner = ty;
+        let mut option_depth = 0;
+        while let syn::Type::Path(tp) = inner
+            && let Some(seg) = tp.path.segments.last()
+            && seg.ident == "Option"
+            && let Some(arg) = first_type_arg(seg)
+        {
+            inner = arg;
+            option_depth += 1;
+        }
+
+        // references and `Cow`s are described by their referent
+        let mut target = inner;
+        let mut cow = None;
+        loop {
+            match target {
+                syn::Type::Reference(r) => target = &r.elem,
+                syn::Type::Path(tp)
+                    if let Some(seg) = tp.path.segments.last()
+                        && seg.ident == "Cow"
+                        && let Some(arg) = first_type_arg(seg) =>
+                {
+                    cow = Some(arg.clone());
+                    target = arg;
+                }
+                _ => break,
+            }
+        }
+        let mut out = FieldType {
+            inner: inner.clone(),
+            option_depth,
+            full: type_string(inner),
+            path: String::new(),
+            name: String::new(),
+            bytes: false,
+            via_vec: false,
+            cow,
+        };
+        match target {
+            syn::Type::Path(tp) => {
+                out.path = tp
+                    .path
+                    .segments
+                    .iter()
+                    .map(|s| s.ident.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::");
+                if let Some(seg) = tp.path.segments.last() {
+                    out.name = seg.ident.to_string();
+                    out.bytes = seg.ident == "Vec" && first_type_arg(seg).is_some_and(is_u8);
+                }
+            }
+            syn::Type::Array(a) => {
+                out.name = "Array".to_string();
+                out.bytes = is_u8(&a.elem);
+                out.via_vec = out.bytes;
+            }
+            syn::Type::Slice(s) => {
+                out.name = "Slice".to_string();
+                out.bytes = is_u8(&s.elem);
+            }
+            syn::Type::Tuple(_) => out.name = "Tuple".to_string(),
+            _ => out.name = "Unknown".to_string(),
+        }
+        if out.library_type() == Some(LibraryType::Bytes) {
+            out.bytes = true;
+            out.via_vec = true;
+        }
+        out
+    }
+
+    /// The library type named by the path. A bare name (`Date`) is taken as imported
+    /// from its crate; a qualified path must start with the crate (`time::Date`),
+    /// optionally re-exported from `sqlx::types`, so `my::Date` is not `time::Date`.
+    pub fn library_type(&self) -> Option<LibraryType> {
+        let segments: Vec<&str> = self.path.split("::").collect();
+        let krate = match segments.as_slice() {
+            [_] => None,
+            ["sqlx", "types", _] => None,
+            ["sqlx", "types", krate, ..] | [krate, ..] => Some(*krate),
+            [] => return None,
+        };
+        let from = |name: &str| krate.is_none_or(|k| k == name);
+        let ty = match self.name.as_str() {
+            "DateTime" if cfg!(feature = "chrono") && from("chrono") => LibraryType::DateTimeTz,
+            "NaiveDateTime" if cfg!(feature = "chrono") && from("chrono") => LibraryType::DateTime,
+            "NaiveDate" if cfg!(feature = "chrono") && from("chrono") => LibraryType::Date,
+            "NaiveTime" if cfg!(feature = "chrono") && from("chrono") => LibraryType::Time,
+            "OffsetDateTime" if cfg!(feature = "time") && from("time") => LibraryType::DateTimeTz,
+            "PrimitiveDateTime" if cfg!(feature = "time") && from("time") => LibraryType::DateTime,
+            "Date" if cfg!(feature = "time") && from("time") => LibraryType::Date,
+            "Time" if cfg!(feature = "time") && from("time") => LibraryType::Time,
+            "Uuid" if cfg!(feature = "uuid") && from("uuid") => LibraryType::Uuid,
+            "Hyphenated" if cfg!(feature = "uuid") && from("uuid") => LibraryType::UuidText,
+            "Decimal" if cfg!(feature = "rust_decimal") && from("rust_decimal") => LibraryType::Decimal,
+            "BigDecimal" if cfg!(feature = "bigdecimal") && from("bigdecimal") => LibraryType::Decimal,
+            "Bytes" if cfg!(feature = "bytes") && from("bytes") => LibraryType::Bytes,
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    /// Describes the column by the type a `transparent` newtype wraps, keeping the
+    /// field's own type for binds and decoding.
+    pub fn describe_as(&mut self, wrapped: &syn::Type) {
+        let wrapped = FieldType::analyze(wrapped);
+        self.path = wrapped.path;
+        self.name = wrapped.name;
+        self.bytes = wrapped.bytes;
+    }
+
+    pub fn is_option(&self) -> bool {
+        self.option_depth > 0
+    }
+
+    /// `&str`, `&[T]` and their `Cow`s, bound through a deref.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.name.as_str(), "str" | "Slice")
+    }
+}
+
+/// Expression path of a tuple struct type, `Id<User>` -> `Id::<User>`, to call its
+/// constructor.
+pub fn ctor_path(ty: &syn::Type) -> Option<syn::Path> {
+    let syn::Type::Path(tp) = ty else {
+        return None;
+    };
+    if tp.qself.is_some() {
+        return None;
+    }
+    let mut path = tp.path.clone();
+    for seg in &mut path.segments {
+        if let syn::PathArguments::AngleBracketed(args) = &mut seg.arguments {
+            args.colon2_token = Some(Default::default());
+        }
+    }
+    Some(path)
+}
+
+/// Whether `ty` names a type parameter or lifetime of `generics`.
+pub fn mentions_generics(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    let names: Vec<String> = generics
+        .params
+        .iter()
+        .filter_map(|p| match p {
+            syn::GenericParam::Type(t) => Some(t.ident.to_string()),
+            syn::GenericParam::Lifetime(l) => Some(l.lifetime.to_string()),
+            syn::GenericParam::Const(_) => None,
+        })
+        .collect();
+    fn scan(tokens: proc_macro2::TokenStream, names: &[String]) -> bool {
+        let mut lifetime = false;
+        for tt in tokens {
+            match tt {
+                proc_macro2::TokenTree::Punct(p) => lifetime = p.as_char() == '\'',
+                proc_macro2::TokenTree::Ident(i) => {
+                    let name = if lifetime { format!("'{}", i) } else { i.to_string() };
+                    if names.contains(&name) {
+                        return true;
+                    }
+                    lifetime = false;
+                }
+                proc_macro2::TokenTree::Group(g) => {
+                    if scan(g.stream(), names) {
+                        return true;
+                    }
+                    lifetime = false;
+                }
+                proc_macro2::TokenTree::Literal(_) => lifetime = false,
+            }
+        }
+        false
+    }
+    !names.is_empty() && scan(ty.to_token_stream(), &names)
+}
@@ -0,0 +1,89 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::emit_error;
+use quote::{format_ident, quote};
+use syn::Expr;
+
+use crate::table_create::Column;
+
+/// Builds the statements of the generated `validate(&self)` for one column. `Option`
+/// fields are only checked when they hold a value.
+pub fn column_checks(naorm: &TokenStream, col: &Column) -> TokenStream {
+    let a = &col.attrs;
+    let name = &col.name;
+    let mut checks: Vec<TokenStream> = Vec::new();
+    if let Some(n) = a.max_len {
+        checks.push(quote! { #naorm::validate::max_len(&mut errors, #name, value, #n); });
+    }
+    if let Some(min) = &a.min {
+        check_bound(col, min, "min");
+        checks.push(quote! { #naorm::validate::min(&mut errors, #name, value, (#min) as _); });
+    }
+    if let Some(max) = &a.max {
+        check_bound(col, max, "max");
+        checks.push(quote! { #naorm::validate::max(&mut errors, #name, value, (#max) as _); });
+    }
+    if let Some(re) = &a.regex {
+        checks.push(quote! {
+            {
+                static RE: ::std::sync::OnceLock<#naorm::__private::regex::Regex> =
+                    ::std::sync::OnceLock::new();
+                let re = RE.get_or_init(|| #naorm::__private::regex::Regex::new(#re).unwrap());
+                #naorm::validate::regex(&mut errors, #name, value, re);
+            }
+        });
+    }
+    if a.not_empty {
+        checks.push(quote! { #naorm::validate::not_empty(&mut errors, #name, value); });
+    }
+    if let Some(f) = &a.validate_with {
+        checks.push(quote! { #naorm::validate::custom(&mut errors, #name, #f(value)); });
+    }
+    if checks.is_empty() {
+        return TokenStream::new();
+    }
+    let ident = format_ident!("{}", col.name);
+    if col.is_option {
+        quote! {
+            if let Some(value) = self.#ident.as_ref() {
+                #(#checks)*
+            }
+        }
+    } else {
+        quote! {
+            {
+                let value = &self.#ident;
+                #(#checks)*
+            }
+        }
+    }
+}
+
+// the bounds are cast to the field type, so an integer field takes integer bounds in
+// its range only: `min = 0.5` would check `>= 0` while the CHECK constraint keeps 0.5
+fn check_bound(col: &Column, bound: &Expr, what: &str) {
+    if !col.is_integer() {
+        return;
+    }
+    let (negative, lit) = match bound {
+        Expr::Unary(u) => (true, &*u.expr),
+        lit => (false, lit),
+    };
+    let Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. }) = lit else {
+        emit_error!(bound, "`{}` of the integer field `{}` must be an integer", what, col.name);
+        return;
+    };
+    let (lo, hi) = match col.ty_name.as_str() {
+        "i8" => (i8::MIN as i128, i8::MAX as i128),
+        "i16" => (i16::MIN as i128, i16::MAX as i128),
+        "i32" => (i32::MIN as i128, i32::MAX as i128),
+        "u8" => (0, u8::MAX as i128),
+        "u16" => (0, u16::MAX as i128),
+        "u32" => (0, u32::MAX as i128),
+        "u64" | "usize" => (0, u64::MAX as i128),
+        _ => (i64::MIN as i128, i64::MAX as i128),
+    };
+    let value = int.base10_parse::<i128>().map(|v| if negative { -v } else { v });
+    if !value.is_ok_and(|v| (lo..=hi).contains(&v)) {
+        emit_error!(bound, "`{}` does not fit the type `{}` of the field `{}`", what, col.ty_name, col.name);
+    }
+}
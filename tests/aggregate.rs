@@ -0,0 +1,49 @@
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow, Debug)]
+#[naorm_cfg(table_name = "sale")]
+struct Sale {
+    #[naorm_cfg(auto_increment)]
+    id: i64,
+    region: Option<String>,
+    units: i32,
+    price: f64,
+}
+
+#[tokio::test]
+async fn test_aggregate_queries() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Sale::create_table(&pool).await.unwrap();
+    let cols = Sale::columns();
+    assert_eq!(Sale::sum_query(cols.units).sql(), "SELECT SUM(units) FROM sale");
+    assert_eq!(Sale::sum_query(cols.units).fetch_one(&pool).await.unwrap(), None);
+    assert_eq!(Sale::count_query().fetch_one(&pool).await.unwrap(), 0);
+
+    for (region, units, price) in [(Some("north"), 3, 2.5), (Some("north"), 5, 1.0), (None, 2, 4.0)] {
+        let mut sale = Sale {
+            id: 0,
+            region: region.map(String::from),
+            units,
+            price,
+        };
+        sale.insert(&pool).await.unwrap();
+    }
+    assert_eq!(Sale::count_query().fetch_one(&pool).await.unwrap(), 3);
+    let units: Option<i64> = Sale::sum_query(cols.units).fetch_one(&pool).await.unwrap();
+    assert_eq!(units, Some(10));
+    let north = Sale::sum_query(cols.price).filter("region = ?").bind("north".to_string()).fetch_one(&pool);
+    assert_eq!(north.await.unwrap(), Some(3.5));
+    assert_eq!(Sale::avg_query(cols.units).fetch_one(&pool).await.unwrap(), Some(10.0 / 3.0));
+    assert_eq!(Sale::min_query(cols.units).fetch_one(&pool).await.unwrap(), Some(2));
+    assert_eq!(Sale::max_query(cols.region).fetch_one(&pool).await.unwrap(), Some("north".to_string()));
+
+    let per_region = Sale::group_by(cols.region).count();
+    assert_eq!(
+        per_region.sql(),
+        "SELECT region, COUNT(*) FROM sale GROUP BY region ORDER BY region"
+    );
+    assert_eq!(
+        per_region.fetch_all(&pool).await.unwrap(),
+        vec![(None, 1), (Some("north".to_string()), 2)]
+    );
+}
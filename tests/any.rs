@@ -0,0 +1,52 @@
+use naorm::{Backend, NaormReg};
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "setting", driver = "any")]
+struct Setting {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(max_len = 40, unique)]
+    name: String,
+    value: Option<String>,
+    #[naorm_cfg(default = 1)]
+    version: i32,
+}
+
+#[tokio::test]
+async fn test_any_driver_picks_dialect_at_runtime() {
+    assert_eq!(
+        Setting::UPDATE_SQL.get(Backend::Postgres),
+        "UPDATE setting SET id = $1, name = $2, value = $3, version = $4 WHERE id = $5"
+    );
+    assert_eq!(Setting::DELETE_SQL.get(Backend::MySql), "DELETE FROM setting WHERE id = ?");
+    assert!(Setting::CREATE_TABLE_SQL.get(Backend::MySql).contains("name VARCHAR(40) NOT NULL"));
+    assert!(Setting::CREATE_TABLE_SQL.get(Backend::Sqlite).contains("CHECK (length(name) <= 40)"));
+    assert_eq!(Setting::schema_for(Backend::Postgres).backend, Backend::Postgres);
+
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    Setting::create_table(&pool).await.unwrap();
+    assert!(Setting::verify_schema(&pool).await.unwrap().is_ok());
+
+    let mut setting = Setting {
+        id: 1,
+        name: "theme".to_string(),
+        value: None,
+        ..Setting::new_default()
+    };
+    setting.insert(&pool).await.unwrap();
+    setting.value = Some("dark".to_string());
+    setting.update(&pool).await.unwrap();
+    assert_eq!(Setting::all_query().fetch_all(&pool).await.unwrap(), vec![setting]);
+
+    let mut conn = pool.acquire().await.unwrap();
+    let backend = Backend::of_any(&conn).unwrap();
+    assert_eq!(backend, Backend::Sqlite);
+    let setting = Setting::all_query().fetch_one(&mut *conn).await.unwrap();
+    setting.delete_query(backend).execute(&mut *conn).await.unwrap();
+    assert!(Setting::all_query().fetch_all(&mut *conn).await.unwrap().is_empty());
+}
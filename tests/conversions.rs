@@ -0,0 +1,62 @@
+use naorm::{Model, NaormReg};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct BookId(i64);
+
+#[derive(Debug, Default, PartialEq)]
+struct Copies(i32);
+
+fn tags_to_text(tags: &[String]) -> String {
+    tags.join(",")
+}
+
+fn tags_from_text(text: String) -> Result<Vec<String>, std::convert::Infallible> {
+    Ok(text.split(',').filter(|t| !t.is_empty()).map(String::from).collect())
+}
+
+#[derive(NaormReg, Debug, PartialEq)]
+#[naorm_cfg(table_name = "shelf_book")]
+struct ShelfBook {
+    #[naorm_cfg(primary_key, transparent = "i64")]
+    id: BookId,
+    #[naorm_cfg(transparent = "i64")]
+    sequel_of: Option<BookId>,
+    #[naorm_cfg(transparent = "i32", default = 3)]
+    copies: Copies,
+    #[naorm_cfg(bind_with = "tags_to_text", decode_with = "tags_from_text", sql_type = "TEXT")]
+    tags: Vec<String>,
+}
+
+#[tokio::test]
+async fn test_transparent_and_custom_conversions() {
+    assert_eq!(
+        ShelfBook::CREATE_TABLE_SQL,
+        "CREATE TABLE IF NOT EXISTS shelf_book (\n    id INTEGER PRIMARY KEY,\n    sequel_of INTEGER,\n    \
+         copies INTEGER NOT NULL DEFAULT 3,\n    tags TEXT NOT NULL\n)"
+    );
+    assert_eq!(ShelfBook::new_default().copies, Copies(3));
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    ShelfBook::create_table(&pool).await.unwrap();
+    assert!(ShelfBook::verify_schema(&pool).await.unwrap().is_ok());
+    let mut first = ShelfBook {
+        id: BookId(1),
+        tags: vec!["classic".to_string()],
+        ..ShelfBook::new_default()
+    };
+    first.insert(&pool).await.unwrap();
+    let mut second = ShelfBook {
+        id: BookId(2),
+        sequel_of: Some(BookId(1)),
+        copies: Copies(1),
+        tags: Vec::new(),
+    };
+    second.insert(&pool).await.unwrap();
+    second.tags = vec!["sequel".to_string(), "signed".to_string()];
+    second.update(&pool).await.unwrap();
+    first.delete_query().execute(&pool).await.unwrap();
+
+    let rows = sqlx::query(ShelfBook::SELECT_SQL).fetch_all(&pool).await.unwrap();
+    let books: Vec<ShelfBook> = rows.iter().map(|row| ShelfBook::from_row_prefixed(row, "").unwrap()).collect();
+    assert_eq!(books, vec![second]);
+}
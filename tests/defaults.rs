@@ -0,0 +1,68 @@
+use naorm::{NaormEnum, NaormReg};
+
+#[derive(NaormEnum, Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Draft,
+    Published,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "post", default)]
+struct Post {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(default = "it's new")]
+    title: String,
+    #[naorm_cfg(default = 3)]
+    votes: i32,
+    #[naorm_cfg(default = 1)]
+    score: f64,
+    #[naorm_cfg(default = true)]
+    visible: bool,
+    #[naorm_cfg(enum, default = "draft")]
+    status: Status,
+    #[naorm_cfg(default = -1)]
+    parent: Option<i64>,
+    #[naorm_cfg(default_sql = "CURRENT_TIMESTAMP")]
+    created_at: String,
+    #[naorm_cfg(default_sql = "lower(hex(randomblob(4)))")]
+    token: Option<String>,
+}
+
+#[tokio::test]
+async fn test_typed_defaults() {
+    let ddl = Post::CREATE_TABLE_SQL;
+    assert!(ddl.contains("title TEXT NOT NULL DEFAULT 'it''s new',"), "{}", ddl);
+    assert!(ddl.contains("votes INTEGER NOT NULL DEFAULT 3,"), "{}", ddl);
+    assert!(ddl.contains("score REAL NOT NULL DEFAULT 1,"), "{}", ddl);
+    assert!(ddl.contains("visible BOOLEAN NOT NULL DEFAULT true,"), "{}", ddl);
+    assert!(ddl.contains("status TEXT NOT NULL DEFAULT 'draft' CHECK"), "{}", ddl);
+    assert!(ddl.contains("parent INTEGER DEFAULT -1,"), "{}", ddl);
+    assert!(ddl.contains("created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,"), "{}", ddl);
+    assert!(ddl.contains("token TEXT DEFAULT (lower(hex(randomblob(4))))"), "{}", ddl);
+    assert_eq!(Post::NAORM_FIELDS[1].default_value, "it's new");
+    assert_eq!(Post::NAORM_FIELDS[5].default_value, "draft");
+
+    let post = Post::default();
+    assert_eq!(
+        (post.title.as_str(), post.votes, post.score, post.visible, post.status, post.parent),
+        ("it's new", 3, 1.0, true, Status::Draft, Some(-1))
+    );
+    assert_eq!((post.created_at.as_str(), post.token.as_deref()), ("", None));
+
+    // the database fills in the same values
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Post::create_table(&pool).await.unwrap();
+    sqlx::query("INSERT INTO post (id) VALUES (7)").execute(&pool).await.unwrap();
+    let stored = Post::all_query().fetch_one(&pool).await.unwrap();
+    assert!(!stored.created_at.is_empty() && stored.token.is_some());
+    assert_eq!(
+        stored,
+        Post {
+            id: 7,
+            created_at: stored.created_at.clone(),
+            token: stored.token.clone(),
+            ..Post::new_default()
+        }
+    );
+}
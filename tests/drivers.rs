@@ -0,0 +1,59 @@
+use naorm::{Backend, Model, NaormEnum, NaormReg};
+
+#[derive(NaormEnum, Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Phone,
+    Laptop,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "device", drivers = "sqlite, postgres")]
+struct Device {
+    #[naorm_cfg(auto_increment)]
+    id: i64,
+    #[naorm_cfg(max_len = 32)]
+    name: String,
+    serial: Option<Vec<u8>>,
+    #[naorm_cfg(enum)]
+    kind: Kind,
+}
+
+#[allow(dead_code)]
+#[derive(NaormReg, sqlx::FromRow, Debug)]
+#[naorm_cfg(table_name = "tag", driver = "all")]
+struct Tag {
+    #[naorm_cfg(primary_key)]
+    name: String,
+}
+
+#[tokio::test]
+async fn test_one_struct_for_several_drivers() {
+    assert_eq!(
+        Device::INSERT_SQL.get(Backend::Postgres),
+        "INSERT INTO device (name, serial, kind) VALUES ($1, $2, $3)"
+    );
+    assert_eq!(Device::INSERT_SQL.get(Backend::Sqlite), "INSERT INTO device (name, serial, kind) VALUES (?, ?, ?)");
+    assert!(Device::CREATE_TABLE_SQL.get(Backend::Postgres).contains("id BIGSERIAL PRIMARY KEY"));
+    assert!(Device::CREATE_TABLE_SQL.get(Backend::Sqlite).contains("serial BLOB,"));
+    // the first listed driver backs `Model`
+    assert_eq!(<Device as Model>::schema().backend, Backend::Sqlite);
+    assert_eq!(Device::schema_for(Backend::Postgres).column("serial").unwrap().sql_type, "BYTEA");
+    assert!(Tag::CREATE_TABLE_SQL.get(Backend::MySql).contains("name VARCHAR(255) PRIMARY KEY"));
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Device::create_table(&pool).await.unwrap();
+    assert!(Device::verify_schema(&pool).await.unwrap().is_ok());
+    let mut device = Device {
+        id: 1,
+        name: "pixel".to_string(),
+        serial: Some(vec![1, 2, 3]),
+        kind: Kind::Phone,
+    };
+    device.insert(&pool).await.unwrap();
+    device.kind = Kind::Laptop;
+    device.update(&pool).await.unwrap();
+    assert_eq!(Device::all_query().fetch_all(&pool).await.unwrap(), vec![device]);
+    let device = Device::all_query().fetch_one(&pool).await.unwrap();
+    device.delete_query().execute(&pool).await.unwrap();
+    assert!(Device::all_query().fetch_optional(&pool).await.unwrap().is_none());
+}
@@ -0,0 +1,104 @@
+use naorm::{NaormEnum, NaormReg};
+
+#[derive(NaormEnum, Debug, Clone, Copy, PartialEq)]
+#[naorm_enum(repr = "text", native, type_name = "book_color")]
+enum Color {
+    Red,
+    #[naorm_enum(rename = "sea-green")]
+    SeaGreen,
+}
+
+#[derive(NaormEnum, Debug, Clone, Copy, PartialEq)]
+#[naorm_enum(repr = "int")]
+enum Priority {
+    Low = 1,
+    High = 5,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "book_note")]
+struct BookNote {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(enum)]
+    color: Color,
+    #[naorm_cfg(enum)]
+    priority: Option<Priority>,
+}
+
+mod pg {
+    use super::Color;
+    use naorm::NaormReg;
+
+    #[allow(dead_code)]
+    #[derive(NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "book_note", driver = "postgres")]
+    pub struct BookNote {
+        #[naorm_cfg(primary_key)]
+        pub id: i64,
+        #[naorm_cfg(enum)]
+        pub color: Color,
+    }
+}
+
+mod my {
+    use super::Color;
+    use naorm::NaormReg;
+
+    #[allow(dead_code)]
+    #[derive(NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "book_note", driver = "mysql")]
+    pub struct BookNote {
+        #[naorm_cfg(primary_key)]
+        pub id: i64,
+        #[naorm_cfg(enum)]
+        pub color: Color,
+    }
+}
+
+#[tokio::test]
+async fn test_enum_columns_round_trip() {
+    assert_eq!(
+        BookNote::CREATE_TABLE_SQL,
+        "CREATE TABLE IF NOT EXISTS book_note (\n    id INTEGER PRIMARY KEY,\n    \
+         color TEXT NOT NULL CHECK (color IN ('red', 'sea-green')),\n    \
+         priority INTEGER CHECK (priority IN (1, 5))\n)"
+    );
+    assert_eq!(Color::SeaGreen.as_sql_str(), "sea-green");
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    BookNote::create_table(&pool).await.unwrap();
+    let mut note = BookNote {
+        id: 1,
+        color: Color::SeaGreen,
+        priority: Some(Priority::High),
+    };
+    note.insert(&pool).await.unwrap();
+    assert_eq!(BookNote::all_query().fetch_one(&pool).await.unwrap(), note);
+    let (raw,): (i64,) = sqlx::query_as("SELECT priority FROM book_note")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(raw, 5);
+
+    // the CHECK constraint rejects values written around the enum
+    assert!(sqlx::query("INSERT INTO book_note (id, color) VALUES (2, 'blue')")
+        .execute(&pool)
+        .await
+        .is_err());
+}
+
+#[test]
+fn test_native_enum_ddl() {
+    assert!(pg::BookNote::CREATE_TABLE_SQL.starts_with(
+        "DO $$ BEGIN CREATE TYPE book_color AS ENUM ('red', 'sea-green'); \
+         EXCEPTION WHEN duplicate_object THEN NULL; END $$;\nCREATE TABLE IF NOT EXISTS book_note"
+    ));
+    assert!(pg::BookNote::CREATE_TABLE_SQL.contains("color book_color NOT NULL"));
+    assert!(my::BookNote::CREATE_TABLE_SQL.contains("color ENUM('red', 'sea-green') NOT NULL"));
+}
+
+#[test]
+fn test_int_enum_discriminant_out_of_range() {
+    trybuild::TestCases::new().compile_fail("tests/ui/enum_discriminant_range.rs");
+}
@@ -0,0 +1,103 @@
+use naorm::{Backend, Model, NaormColumns, NaormReg};
+
+#[derive(NaormColumns, Debug, Default, Clone, PartialEq)]
+struct Address {
+    #[naorm_cfg(max_len = 40)]
+    street: String,
+    city: String,
+    zip: Option<String>,
+}
+
+#[derive(NaormReg, Debug, PartialEq)]
+#[naorm_cfg(table_name = "customer")]
+struct Customer {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(flatten, prefix = "billing_")]
+    billing: Address,
+    #[naorm_cfg(flatten, prefix = "shipping_")]
+    shipping: Address,
+    note: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(NaormReg, Debug)]
+#[naorm_cfg(table_name = "warehouse", drivers = "sqlite, postgres")]
+struct Warehouse {
+    #[naorm_cfg(auto_increment)]
+    id: i64,
+    #[naorm_cfg(flatten)]
+    address: Address,
+    capacity: i32,
+}
+
+#[test]
+fn test_flatten_sql() {
+    assert_eq!(
+        Customer::SELECT_SQL,
+        "SELECT id, billing_street, billing_city, billing_zip, shipping_street, shipping_city, shipping_zip, note \
+         FROM customer"
+    );
+    assert_eq!(
+        Customer::INSERT_SQL,
+        "INSERT INTO customer (id, billing_street, billing_city, billing_zip, shipping_street, shipping_city, \
+         shipping_zip, note) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    );
+    assert_eq!(
+        Customer::CREATE_TABLE_SQL,
+        "CREATE TABLE IF NOT EXISTS customer (\n    id INTEGER PRIMARY KEY,\n    billing_street VARCHAR(40) NOT NULL \
+         CHECK (length(billing_street) <= 40),\n    billing_city TEXT NOT NULL,\n    billing_zip TEXT,\n    \
+         shipping_street VARCHAR(40) NOT NULL CHECK (length(shipping_street) <= 40),\n    shipping_city TEXT NOT NULL,\n    \
+         shipping_zip TEXT,\n    note TEXT\n)"
+    );
+    assert_eq!(<Customer as Model>::COLUMNS.len(), 8);
+    assert_eq!(Customer::NAORM_FIELDS[3].name, "billing_zip");
+    assert!(Customer::NAORM_FIELDS[3].is_option);
+    // postgres placeholders are numbered across the group columns
+    assert_eq!(
+        Warehouse::INSERT_SQL.get(Backend::Postgres),
+        "INSERT INTO warehouse (street, city, zip, capacity) VALUES ($1, $2, $3, $4)"
+    );
+    assert_eq!(
+        Warehouse::UPDATE_SQL.get(Backend::Postgres),
+        "UPDATE warehouse SET street = $1, city = $2, zip = $3, capacity = $4 WHERE id = $5"
+    );
+}
+
+#[tokio::test]
+async fn test_flatten_round_trip() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Customer::create_table(&pool).await.unwrap();
+    assert!(Customer::verify_schema(&pool).await.unwrap().is_ok());
+    let billing = Address {
+        street: "1 Main St".to_string(),
+        city: "Springfield".to_string(),
+        zip: Some("12345".to_string()),
+    };
+    let mut customer = Customer {
+        id: 1,
+        billing: billing.clone(),
+        shipping: billing,
+        note: None,
+    };
+    customer.insert(&pool).await.unwrap();
+    customer.shipping.city = "Shelbyville".to_string();
+    customer.shipping.zip = None;
+    customer.update(&pool).await.unwrap();
+    assert_eq!(Customer::all_query().fetch_all(&pool).await.unwrap(), vec![customer]);
+
+    let mut invalid = Customer::new_default();
+    invalid.billing.street = "x".repeat(41);
+    let errors = invalid.validate().unwrap_err();
+    assert_eq!(errors.errors()[0].field, "street");
+
+    Warehouse::create_table(&pool).await.unwrap();
+    let mut warehouse = Warehouse {
+        id: 0,
+        address: Address::default(),
+        capacity: 10,
+    };
+    warehouse.insert(&pool).await.unwrap();
+    let stored = Warehouse::all_query::<sqlx::Sqlite>().fetch_one(&pool).await.unwrap();
+    assert_eq!((stored.id, stored.capacity), (1, 10));
+}
@@ -0,0 +1,106 @@
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "book_note")]
+struct BookNote {
+    #[naorm_cfg(primary_key, auto_increment)]
+    id: i64,
+    book_id: i64,
+    #[naorm_cfg(fulltext)]
+    content: String,
+    #[naorm_cfg(fulltext)]
+    note: Option<String>,
+}
+
+async fn titles(term: &str, pool: &sqlx::SqlitePool) -> Vec<String> {
+    BookNote::search_query(term)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|n| n.content)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_fts5_search_stays_in_sync() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    BookNote::create_table(&pool).await.unwrap();
+    for (content, note) in [
+        ("the spice must flow", Some("spice spice")),
+        ("desert planet", None),
+        ("a little spice", None),
+    ] {
+        let mut n = BookNote {
+            id: 0,
+            book_id: 1,
+            content: content.to_string(),
+            note: note.map(str::to_string),
+        };
+        n.insert(&pool).await.unwrap();
+    }
+
+    assert_eq!(titles("spice", &pool).await, ["the spice must flow", "a little spice"]);
+
+    let hits = BookNote::search_hits_query("desert").fetch_all(&pool).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].model.id, 2);
+    assert_eq!(hits[0].highlight.as_deref(), Some("<b>desert</b> planet"));
+    assert!(hits[0].snippet.as_deref().unwrap().contains("<b>desert</b>"));
+
+    // update and delete triggers keep the index current
+    let mut n = BookNote::all_query().fetch_all(&pool).await.unwrap().remove(1);
+    n.content = "ocean planet".to_string();
+    n.update(&pool).await.unwrap();
+    assert!(titles("desert", &pool).await.is_empty());
+    assert_eq!(titles("ocean", &pool).await, ["ocean planet"]);
+    n.delete_query().execute(&pool).await.unwrap();
+    assert!(titles("planet", &pool).await.is_empty());
+}
+
+mod pg {
+    use naorm::NaormReg;
+
+    #[allow(dead_code)]
+    #[derive(NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "book_note", driver = "postgres")]
+    pub struct BookNote {
+        #[naorm_cfg(primary_key, auto_increment)]
+        pub id: i64,
+        #[naorm_cfg(fulltext)]
+        pub content: String,
+    }
+}
+
+mod my {
+    use naorm::NaormReg;
+
+    #[allow(dead_code)]
+    #[derive(NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "book_note", driver = "mysql")]
+    pub struct BookNote {
+        #[naorm_cfg(primary_key, auto_increment)]
+        pub id: i64,
+        #[naorm_cfg(fulltext)]
+        pub content: String,
+    }
+}
+
+#[test]
+fn test_search_sql_per_driver() {
+    assert_eq!(
+        pg::BookNote::SEARCH_SQL,
+        "SELECT book_note.id, book_note.content FROM book_note \
+         WHERE book_note.naorm_tsv @@ websearch_to_tsquery('english', $1) \
+         ORDER BY ts_rank(book_note.naorm_tsv, websearch_to_tsquery('english', $1)) DESC"
+    );
+    assert_eq!(
+        my::BookNote::SEARCH_SQL,
+        "SELECT book_note.id, book_note.content FROM book_note \
+         WHERE MATCH (book_note.content) AGAINST (? IN NATURAL LANGUAGE MODE) \
+         ORDER BY MATCH (book_note.content) AGAINST (? IN NATURAL LANGUAGE MODE) DESC"
+    );
+    // same signature on every driver
+    let _: sqlx::query::QueryAs<'_, sqlx::Postgres, pg::BookNote, _> = pg::BookNote::search_query("spice");
+    let _: sqlx::query::QueryAs<'_, sqlx::MySql, my::BookNote, _> = my::BookNote::search_query("spice");
+}
@@ -0,0 +1,91 @@
+use std::borrow::Cow;
+
+use naorm::{Model, NaormReg};
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "envelope")]
+struct Envelope<T>
+where
+    T: Clone,
+{
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(sql_type = "INTEGER")]
+    payload: T,
+    #[naorm_cfg(sql_type = "INTEGER")]
+    retry: Option<T>,
+}
+
+#[derive(NaormReg, Debug, PartialEq)]
+#[naorm_cfg(table_name = "label")]
+struct Label<'a> {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(max_len = 20)]
+    name: Cow<'a, str>,
+    note: Option<Cow<'a, str>>,
+}
+
+/// Borrowed fields make an insert-only struct.
+#[derive(NaormReg)]
+#[naorm_cfg(table_name = "label")]
+struct NewLabel<'a> {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    name: &'a str,
+    note: Option<&'a str>,
+}
+
+#[tokio::test]
+async fn test_generic_struct_round_trip() {
+    assert_eq!(
+        Envelope::<i32>::CREATE_TABLE_SQL,
+        "CREATE TABLE IF NOT EXISTS envelope (\n    id INTEGER PRIMARY KEY,\n    payload INTEGER NOT NULL,\n    \
+         retry INTEGER\n)"
+    );
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Envelope::<i32>::create_table(&pool).await.unwrap();
+    let mut envelope = Envelope {
+        id: 1,
+        payload: 7i32,
+        retry: Some(2),
+    };
+    envelope.insert(&pool).await.unwrap();
+    envelope.payload = 8;
+    envelope.update(&pool).await.unwrap();
+    assert_eq!(Envelope::<i32>::all_query().fetch_all(&pool).await.unwrap(), vec![envelope]);
+}
+
+#[tokio::test]
+async fn test_borrowed_fields() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Label::create_table(&pool).await.unwrap();
+    assert!(Label::verify_schema(&pool).await.unwrap().is_ok());
+    let name = String::from("urgent");
+    let mut label = NewLabel {
+        id: 1,
+        name: &name,
+        note: Some("red"),
+    };
+    label.insert(&pool).await.unwrap();
+    let mut label = Label {
+        id: 2,
+        name: Cow::Borrowed("later"),
+        note: None,
+    };
+    label.insert(&pool).await.unwrap();
+
+    let rows = sqlx::query(Label::SELECT_SQL).fetch_all(&pool).await.unwrap();
+    let labels: Vec<Label<'static>> = rows.iter().map(|row| Label::from_row_prefixed(row, "").unwrap()).collect();
+    assert_eq!(
+        labels,
+        vec![
+            Label {
+                id: 1,
+                name: Cow::Borrowed("urgent"),
+                note: Some(Cow::Borrowed("red")),
+            },
+            label,
+        ]
+    );
+}
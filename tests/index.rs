@@ -0,0 +1,59 @@
+use naorm::{Model, NaormReg};
+
+#[allow(dead_code)]
+#[derive(NaormReg, sqlx::FromRow, Debug)]
+#[naorm_cfg(table_name = "book_note")]
+#[naorm_cfg(index(name = "book_note_lookup", columns = "book_id, color"))]
+struct BookNote {
+    #[naorm_cfg(primary_key, auto_increment)]
+    id: i64,
+    #[naorm_cfg(index)]
+    book_id: i64,
+    #[naorm_cfg(unique)]
+    slug: String,
+    color: Option<String>,
+}
+
+#[tokio::test]
+async fn test_create_table_creates_indexes() {
+    assert_eq!(
+        BookNote::CREATE_INDEX_SQL,
+        &[
+            "CREATE INDEX IF NOT EXISTS book_note_book_id_index ON book_note (book_id)",
+            "CREATE UNIQUE INDEX IF NOT EXISTS book_note_slug_unique ON book_note (slug)",
+            "CREATE INDEX IF NOT EXISTS book_note_lookup ON book_note (book_id, color)",
+        ]
+    );
+    let schema = BookNote::schema();
+    assert_eq!(schema.indexes.len(), 3);
+    assert_eq!(schema.indexes[2].columns, vec!["book_id", "color"]);
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    BookNote::create_table(&pool).await.unwrap();
+    // indexes are created with IF NOT EXISTS, running it twice is fine
+    BookNote::create_table(&pool).await.unwrap();
+    let names: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'book_note' AND sql IS NOT NULL ORDER BY name",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        names,
+        vec![
+            ("book_note_book_id_index".to_string(),),
+            ("book_note_lookup".to_string(),),
+            ("book_note_slug_unique".to_string(),),
+        ]
+    );
+
+    let mut note = BookNote {
+        id: 0,
+        book_id: 1,
+        slug: "dune".to_string(),
+        color: None,
+    };
+    note.insert(&pool).await.unwrap();
+    // the unique index rejects a second row with the same slug
+    assert!(note.insert(&pool).await.is_err());
+}
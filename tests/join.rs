@@ -0,0 +1,73 @@
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "book")]
+struct Book {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    title: String,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "book_note")]
+struct BookNote {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    book_id: i64,
+    content: String,
+}
+
+#[tokio::test]
+async fn test_join_decodes_both_sides() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Book::create_table(&pool).await.unwrap();
+    BookNote::create_table(&pool).await.unwrap();
+    let mut dune = Book {
+        id: 10,
+        title: "Dune".to_string(),
+    };
+    dune.insert(&pool).await.unwrap();
+    let mut notes = vec![
+        BookNote {
+            id: 1,
+            book_id: 10,
+            content: "spice".to_string(),
+        },
+        BookNote {
+            id: 2,
+            book_id: 99,
+            content: "orphan".to_string(),
+        },
+    ];
+    for note in &mut notes {
+        note.insert(&pool).await.unwrap();
+    }
+
+    let join = naorm::join::inner::<BookNote, Book>("book_note.book_id = book.id");
+    assert!(join.sql().starts_with(
+        "SELECT book_note.id AS book_note__id, book_note.book_id AS book_note__book_id"
+    ));
+    let rows = join.fetch_all(&pool).await.unwrap();
+    assert_eq!(rows, vec![(notes[0].clone(), dune.clone())]);
+
+    let rows = naorm::join::left::<BookNote, Book>("book_note.book_id = book.id")
+        .order_by("book_note.id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            (notes[0].clone(), Some(dune.clone())),
+            (notes[1].clone(), None)
+        ]
+    );
+
+    let row = naorm::join::left::<BookNote, Book>("book_note.book_id = book.id")
+        .filter("book_note.content = ?")
+        .bind("orphan".to_string())
+        .fetch_optional(&pool)
+        .await
+        .unwrap();
+    assert_eq!(row, Some((notes[1].clone(), None)));
+}
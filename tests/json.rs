@@ -0,0 +1,69 @@
+use naorm::NaormReg;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Settings {
+    theme: String,
+    tags: Vec<String>,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "reader")]
+struct Reader {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(json)]
+    #[sqlx(json)]
+    settings: Settings,
+    #[sqlx(json(nullable))]
+    extra: Option<serde_json::Value>,
+}
+
+#[tokio::test]
+async fn test_json_columns_round_trip() {
+    assert!(Reader::CREATE_TABLE_SQL.contains("settings TEXT NOT NULL"));
+    assert_eq!(naorm::json::pg_path("$.tags[0].name"), ["tags", "0", "name"]);
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Reader::create_table(&pool).await.unwrap();
+    let mut readers = vec![
+        Reader {
+            id: 1,
+            settings: Settings {
+                theme: "dark".to_string(),
+                tags: vec!["sf".to_string()],
+            },
+            extra: Some(serde_json::json!({ "font": 12 })),
+        },
+        Reader {
+            id: 2,
+            settings: Settings {
+                theme: "light".to_string(),
+                tags: vec![],
+            },
+            extra: None,
+        },
+    ];
+    for r in &mut readers {
+        r.insert(&pool).await.unwrap();
+    }
+    assert_eq!(Reader::all_query().fetch_all(&pool).await.unwrap(), readers);
+
+    let dark = Reader::settings_path_query("theme", "dark")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(dark, readers[..1]);
+    let sf = Reader::settings_path_query("tags[0]", "sf")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(sf.id, 1);
+    let big = Reader::extra_path_query("font", "12").fetch_all(&pool).await.unwrap();
+    assert_eq!(big.len(), 1);
+}
+
+#[test]
+fn test_json_field_requires_sqlx_json() {
+    trybuild::TestCases::new().compile_fail("tests/ui/json_without_sqlx_json.rs");
+}
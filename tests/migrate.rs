@@ -0,0 +1,119 @@
+use naorm::migrate::Migrator;
+use naorm::Model;
+
+mod v1 {
+    use naorm::NaormReg;
+
+    #[derive(NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "note")]
+    pub struct Note {
+        #[naorm_cfg(primary_key, auto_increment)]
+        pub id: i64,
+        pub title: String,
+        pub body: String,
+    }
+}
+
+mod v2 {
+    use naorm::NaormReg;
+
+    #[allow(dead_code)]
+    #[derive(NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "note")]
+    pub struct Note {
+        #[naorm_cfg(primary_key, auto_increment)]
+        pub id: i64,
+        #[naorm_cfg(renamed_from = "title")]
+        pub headline: String,
+        pub body: String,
+        pub color: Option<String>,
+    }
+}
+
+mod v3 {
+    use naorm::NaormReg;
+
+    #[allow(dead_code)]
+    #[derive(NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "note")]
+    pub struct Note {
+        #[naorm_cfg(primary_key, auto_increment)]
+        pub id: i64,
+        pub headline: String,
+        pub body: Option<String>,
+        pub color: Option<String>,
+    }
+}
+
+async fn run(dir: &std::path::Path, pool: &sqlx::SqlitePool) {
+    sqlx::migrate::Migrator::new(dir)
+        .await
+        .unwrap()
+        .run(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_generated_migrations_apply_and_revert() {
+    let dir = std::env::temp_dir().join(format!("naorm_migrate_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+    let m1 = Migrator::new(&dir)
+        .version("1")
+        .generate("create note", &[v1::Note::schema()])
+        .unwrap()
+        .unwrap();
+    assert!(m1.up_path.ends_with("1_create_note.up.sql"));
+    assert!(m1.down.contains("DROP TABLE note;"));
+    run(&dir, &pool).await;
+    let mut note = v1::Note {
+        id: 0,
+        title: "Dune".to_string(),
+        body: "spice".to_string(),
+    };
+    note.insert(&pool).await.unwrap();
+
+    // unchanged structs produce no migration
+    assert!(Migrator::new(&dir)
+        .generate("noop", &[v1::Note::schema()])
+        .unwrap()
+        .is_none());
+
+    let m2 = Migrator::new(&dir)
+        .version("2")
+        .generate("rename title", &[v2::Note::schema()])
+        .unwrap()
+        .unwrap();
+    assert!(m2.up.contains("ALTER TABLE note RENAME COLUMN title TO headline;"));
+    assert!(m2.up.contains("ALTER TABLE note ADD COLUMN color TEXT;"));
+    assert!(m2.down.contains("ALTER TABLE note RENAME COLUMN headline TO title;"));
+    assert!(m2.down.contains("ALTER TABLE note DROP COLUMN color;"));
+    run(&dir, &pool).await;
+    let row = v2::Note::all_query().fetch_one(&pool).await.unwrap();
+    assert_eq!(row.headline, "Dune");
+    assert_eq!(row.color, None);
+
+    // sqlite can not ALTER COLUMN, nullability changes rebuild the table
+    let m3 = Migrator::new(&dir)
+        .version("3")
+        .generate("optional body", &[v3::Note::schema()])
+        .unwrap()
+        .unwrap();
+    assert!(m3.up.contains("CREATE TABLE note__naorm_new ("));
+    run(&dir, &pool).await;
+    let row = v3::Note::all_query().fetch_one(&pool).await.unwrap();
+    assert_eq!((row.id, row.body.as_deref()), (1, Some("spice")));
+
+    sqlx::migrate::Migrator::new(dir.as_path())
+        .await
+        .unwrap()
+        .undo(&pool, 1)
+        .await
+        .unwrap();
+    let row = v1::Note::all_query().fetch_one(&pool).await.unwrap();
+    assert_eq!((row.title.as_str(), row.body.as_str()), ("Dune", "spice"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
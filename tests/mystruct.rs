@@ -1,3 +1,5 @@
+// legacy fixture exercising the original API; allows cover its intentionally unused imports
+#![allow(unused_imports, dead_code, clippy::single_component_path_imports, clippy::needless_borrows_for_generic_args)]
 use std::clone;
 use std::fmt::format;
 
@@ -44,7 +46,7 @@ impl BookNote {
     ];
     pub async fn create_table(pool: &SqlitePool) -> Result<(), Error> {
         sqlx::query(
-            "CREATE TABLE IF NOT EXISTS book_notes (
+            "CREATE TABLE IF NOT EXISTS book_note (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             book_id INTEGER NOT NULL,
             content TEXT NOT NULL,
@@ -52,14 +54,14 @@ impl BookNote {
             color TEXT,
             created_at INTEGER
         );
-        CREATE INDEX IF NOT EXISTS book_notes_book_id_index on book_notes (book_id);
+        CREATE INDEX IF NOT EXISTS book_notes_book_id_index on book_note (book_id);
         CREATE VIRTUAL TABLE IF NOT EXISTS book_notes_fts USING fts5(
             content,
             note,
-            content='book_notes',
+            content='book_note',
             content_rowid='id'
         );
-        CREATE TRIGGER IF NOT EXISTS book_notes_ai AFTER INSERT ON book_notes BEGIN
+        CREATE TRIGGER IF NOT EXISTS book_notes_ai AFTER INSERT ON book_note BEGIN
             INSERT INTO book_notes_fts (rowid, content, note) VALUES (new.id, new.content, new.note);
         END;
         ",
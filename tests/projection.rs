@@ -0,0 +1,58 @@
+use naorm::{NaormProjection, NaormReg};
+
+#[derive(NaormReg, sqlx::FromRow, Debug)]
+#[naorm_cfg(table_name = "memo")]
+struct Memo {
+    #[naorm_cfg(auto_increment)]
+    id: i64,
+    content: String,
+    pinned: bool,
+    color: Option<String>,
+}
+
+#[derive(NaormProjection, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(model = Memo)]
+struct MemoSummary {
+    id: i64,
+    content: String,
+    // a NOT NULL column may be read as an `Option`
+    pinned: Option<bool>,
+}
+
+#[tokio::test]
+async fn test_projection_selects_its_columns() {
+    assert_eq!(MemoSummary::SELECT_SQL, "SELECT id, content, pinned FROM memo");
+    assert_eq!(MemoSummary::COLUMNS, &["id", "content", "pinned"]);
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Memo::create_table(&pool).await.unwrap();
+    for (content, pinned) in [("groceries", true), ("call back", false)] {
+        let mut memo = Memo {
+            id: 0,
+            content: content.to_string(),
+            pinned,
+            color: Some("red".to_string()),
+        };
+        memo.insert(&pool).await.unwrap();
+    }
+    let all = MemoSummary::all_query().fetch_all(&pool).await.unwrap();
+    assert_eq!(all.len(), 2);
+    let pinned = MemoSummary::filter_query(&format!("{} WHERE pinned = ?", MemoSummary::SELECT_SQL))
+        .bind(true)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        pinned,
+        vec![MemoSummary {
+            id: 1,
+            content: "groceries".to_string(),
+            pinned: Some(true),
+        }]
+    );
+}
+
+#[test]
+fn test_projection_rejects_flatten_fields() {
+    trybuild::TestCases::new().compile_fail("tests/ui/projection_flatten_field.rs");
+}
@@ -0,0 +1,201 @@
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "book", has_many(BookNote, foreign_key = "book_id"))]
+#[naorm_cfg(has_one(BookNote, foreign_key = "quoted_book_id", name = "quote"))]
+struct Book {
+    #[naorm_cfg(primary_key, auto_increment)]
+    id: i64,
+    title: String,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone)]
+#[naorm_cfg(table_name = "book_note")]
+struct BookNote {
+    #[naorm_cfg(primary_key, auto_increment)]
+    id: i64,
+    #[naorm_cfg(belongs_to = "Book", on_delete = "cascade")]
+    book_id: i64,
+    #[naorm_cfg(belongs_to = "Book", references = "book(id)", on_delete = "set_null")]
+    quoted_book_id: Option<i64>,
+    content: String,
+}
+
+async fn setup() -> sqlx::SqlitePool {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&pool)
+        .await
+        .unwrap();
+    Book::create_table(&pool).await.unwrap();
+    BookNote::create_table(&pool).await.unwrap();
+    pool
+}
+
+#[tokio::test]
+async fn test_belongs_to_loads_parent() {
+    assert!(BookNote::CREATE_TABLE_SQL
+        .contains("FOREIGN KEY (book_id) REFERENCES book (id) ON DELETE CASCADE"));
+    assert!(BookNote::CREATE_TABLE_SQL
+        .contains("FOREIGN KEY (quoted_book_id) REFERENCES book (id) ON DELETE SET NULL"));
+
+    let pool = setup().await;
+    let mut book = Book {
+        id: 0,
+        title: "Dune".to_string(),
+    };
+    book.insert(&pool).await.unwrap();
+    book.id = 1;
+
+    let mut note = BookNote {
+        id: 0,
+        book_id: 1,
+        quoted_book_id: None,
+        content: "spice".to_string(),
+    };
+    note.insert(&pool).await.unwrap();
+
+    assert_eq!(note.load_book(&pool).await.unwrap(), book);
+    assert_eq!(note.book_query().fetch_one(&pool).await.unwrap(), book);
+    assert_eq!(note.load_quoted_book(&pool).await.unwrap(), None);
+
+    // unknown parents are rejected by the generated FOREIGN KEY
+    note.book_id = 42;
+    assert!(note.insert(&pool).await.is_err());
+
+    let stored = BookNote::all_query().fetch_all(&pool).await.unwrap();
+    assert_eq!(stored[0].id, 1);
+    book.delete_query().execute(&pool).await.unwrap();
+    assert!(BookNote::all_query().fetch_all(&pool).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_has_many_batched_loading() {
+    let pool = setup().await;
+    let mut books = Vec::new();
+    for (i, title) in ["Dune", "Emma", "Ulysses"].into_iter().enumerate() {
+        let mut book = Book {
+            id: 0,
+            title: title.to_string(),
+        };
+        book.insert(&pool).await.unwrap();
+        book.id = i as i64 + 1;
+        books.push(book);
+    }
+    for (book_id, content) in [(1, "a"), (1, "b"), (2, "c")] {
+        let mut note = BookNote {
+            id: 0,
+            book_id,
+            quoted_book_id: (content == "c").then_some(book_id),
+            content: content.to_string(),
+        };
+        note.insert(&pool).await.unwrap();
+    }
+
+    let notes = books[0].load_notes(&pool).await.unwrap();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(books[1].notes_query().fetch_all(&pool).await.unwrap()[0].content, "c");
+
+    let grouped = Book::load_notes_for(&books, &pool).await.unwrap();
+    assert_eq!(grouped[&1].len(), 2);
+    assert_eq!(grouped[&2][0].content, "c");
+    assert!(!grouped.contains_key(&3));
+    assert!(Book::load_notes_for(&[], &pool).await.unwrap().is_empty());
+
+    let quotes = Book::load_quote_for(&books, &pool).await.unwrap();
+    assert_eq!(quotes.len(), 1);
+    assert_eq!(quotes[&2].content, "c");
+    assert!(books[0].load_quote(&pool).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_has_many_loading_in_chunks() {
+    let pool = setup().await;
+    let count = naorm::sql::IN_LIST_CHUNK as i64 * 2 + 1;
+    let mut tx = pool.begin().await.unwrap();
+    let mut books = Vec::new();
+    for id in 1..=count {
+        let mut book = Book {
+            id: 0,
+            title: format!("book {id}"),
+        };
+        book.insert(&mut *tx).await.unwrap();
+        book.id = id;
+        let mut note = BookNote {
+            id: 0,
+            book_id: id,
+            quoted_book_id: None,
+            content: format!("note {id}"),
+        };
+        note.insert(&mut *tx).await.unwrap();
+        books.push(book);
+    }
+    tx.commit().await.unwrap();
+
+    let grouped = Book::load_notes_for(&books, &pool).await.unwrap();
+    assert_eq!(grouped.len(), books.len());
+    assert_eq!(grouped[&count][0].content, format!("note {count}"));
+    let mut conn = pool.acquire().await.unwrap();
+    assert_eq!(Book::load_quote_for(&books, &mut *conn).await.unwrap().len(), 0);
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "tag")]
+struct Tag {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    name: String,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone)]
+#[naorm_cfg(table_name = "note")]
+#[naorm_cfg(many_to_many(Tag, through = "note_tag", local = "note_id", foreign = "tag_id"))]
+struct Note {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    content: String,
+}
+
+#[tokio::test]
+async fn test_many_to_many_through_join_table() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Tag::create_table(&pool).await.unwrap();
+    Note::create_table(&pool).await.unwrap();
+    sqlx::raw_sql("CREATE TABLE note_tag (note_id INTEGER NOT NULL, tag_id INTEGER NOT NULL, PRIMARY KEY (note_id, tag_id))")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut tags = Vec::new();
+    for (id, name) in [(1, "sf"), (2, "classic"), (3, "long")] {
+        let mut tag = Tag {
+            id,
+            name: name.to_string(),
+        };
+        tag.insert(&pool).await.unwrap();
+        tags.push(tag);
+    }
+    let mut note = Note {
+        id: 7,
+        content: "spice".to_string(),
+    };
+    note.insert(&pool).await.unwrap();
+
+    note.attach_tag(&tags[0], &pool).await.unwrap();
+    note.attach_tag(&tags[1], &pool).await.unwrap();
+    assert_eq!(note.load_tags(&pool).await.unwrap(), tags[..2]);
+
+    note.detach_tag(&tags[0], &pool).await.unwrap();
+    assert_eq!(note.tags_query().fetch_all(&pool).await.unwrap(), tags[1..2]);
+
+    note.sync_tags(&[tags[2].clone(), tags[0].clone(), tags[2].clone()], &pool)
+        .await
+        .unwrap();
+    let mut synced = note.load_tags(&pool).await.unwrap();
+    synced.sort_by_key(|t| t.id);
+    assert_eq!(synced, vec![tags[0].clone(), tags[2].clone()]);
+
+    note.sync_tags(&[], &pool).await.unwrap();
+    assert!(note.load_tags(&pool).await.unwrap().is_empty());
+    assert_eq!(Note::all_query().fetch_one(&pool).await.unwrap().content, "spice");
+}
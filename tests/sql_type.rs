@@ -0,0 +1,57 @@
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "account")]
+struct Account {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(sql_type = "VARCHAR(64)")]
+    login: String,
+    #[naorm_cfg(sql_type = "SMALLINT")]
+    level: i16,
+    #[naorm_cfg(sql_type(postgres = "CITEXT", mysql = "VARCHAR(320)"))]
+    email: Option<String>,
+}
+
+mod pg {
+    #[allow(dead_code)]
+    #[derive(naorm::NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "account", driver = "postgres")]
+    pub struct Account {
+        #[naorm_cfg(auto_increment, sql_type(postgres = "SMALLSERIAL"))]
+        pub id: i16,
+        #[naorm_cfg(sql_type = "VARCHAR(64)", sql_type(postgres = "CITEXT"))]
+        pub email: String,
+    }
+}
+
+#[tokio::test]
+async fn test_sql_type_overrides() {
+    assert_eq!(
+        Account::CREATE_TABLE_SQL,
+        "CREATE TABLE IF NOT EXISTS account (\n    id INTEGER PRIMARY KEY,\n    login VARCHAR(64) NOT NULL,\n    \
+         level SMALLINT NOT NULL,\n    email TEXT\n)"
+    );
+    let types: Vec<(&str, &str)> = Account::NAORM_FIELDS.iter().map(|f| (f.name, f.sql_type)).collect();
+    assert_eq!(
+        types,
+        vec![("id", "INTEGER"), ("login", "VARCHAR(64)"), ("level", "SMALLINT"), ("email", "TEXT")]
+    );
+    assert_eq!(
+        pg::Account::CREATE_TABLE_SQL,
+        "CREATE TABLE IF NOT EXISTS account (\n    id SMALLSERIAL PRIMARY KEY,\n    email CITEXT NOT NULL\n)"
+    );
+    assert_eq!(pg::Account::NAORM_FIELDS[1].sql_type, "CITEXT");
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Account::create_table(&pool).await.unwrap();
+    assert!(Account::verify_schema(&pool).await.unwrap().is_ok());
+    let mut account = Account {
+        id: 1,
+        login: "ada".to_string(),
+        level: 3,
+        email: None,
+    };
+    account.insert(&pool).await.unwrap();
+    assert_eq!(Account::all_query().fetch_one(&pool).await.unwrap(), account);
+}
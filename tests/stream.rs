@@ -0,0 +1,53 @@
+use futures_util::TryStreamExt;
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "reading")]
+struct Reading {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    value: f64,
+}
+
+async fn pool_with_readings(count: i64) -> sqlx::SqlitePool {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Reading::create_table(&pool).await.unwrap();
+    for id in (1..=count).rev() {
+        let mut reading = Reading { id, value: id as f64 / 2.0 };
+        reading.insert(&pool).await.unwrap();
+    }
+    pool
+}
+
+#[tokio::test]
+async fn test_stream_rows_and_chunks() {
+    let pool = pool_with_readings(5).await;
+    let all: Vec<Reading> = Reading::stream_all(&pool).try_collect().await.unwrap();
+    assert_eq!(all.len(), 5);
+    let sql = format!("{} WHERE value > ? ORDER BY id", Reading::SELECT_SQL);
+    let high: Vec<Reading> = Reading::stream_filter(&sql, |q| q.bind(1.0), &pool).try_collect().await.unwrap();
+    assert_eq!(high.iter().map(|r| r.id).collect::<Vec<_>>(), vec![3, 4, 5]);
+    let sizes: Vec<usize> = naorm::stream::chunks(Reading::stream_filter(&sql, |q| q.bind(1.0), &pool), 2)
+        .map_ok(|batch| batch.len())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(sizes, vec![2, 1]);
+    let sizes: Vec<usize> = Reading::stream_chunks(&pool, 2).map_ok(|batch| batch.len()).try_collect().await.unwrap();
+    assert_eq!(sizes, vec![2, 2, 1]);
+}
+
+#[tokio::test]
+async fn test_stream_keyset_batches() {
+    let pool = pool_with_readings(5).await;
+    let batches: Vec<Vec<i64>> = Reading::stream_keyset(&pool, 2)
+        .map_ok(|batch| batch.iter().map(|r| r.id).collect())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(batches, vec![vec![1, 2], vec![3, 4], vec![5]]);
+    // a full last batch ends with one empty query
+    let pool = pool_with_readings(4).await;
+    let batches: Vec<Vec<Reading>> = Reading::stream_keyset(&pool, 2).try_collect().await.unwrap();
+    assert_eq!(batches.len(), 2);
+}
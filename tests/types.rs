@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "attachment")]
+struct Attachment {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    data: Vec<u8>,
+    #[sqlx(try_from = "Vec<u8>")]
+    checksum: [u8; 4],
+    thumbnail: Option<Vec<u8>>,
+    caption: Option<Option<String>>,
+    #[naorm_cfg(json)]
+    #[sqlx(json)]
+    meta: std::collections::HashMap<String, i64>,
+}
+
+#[tokio::test]
+async fn test_full_type_paths_and_byte_types() {
+    let fields: Vec<(&str, &str, bool)> = Attachment::NAORM_FIELDS
+        .iter()
+        .map(|f| (f.name, f.field_type, f.is_option))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("id", "i64", false),
+            ("data", "Vec<u8>", false),
+            ("checksum", "[u8; 4]", false),
+            ("thumbnail", "Vec<u8>", true),
+            ("caption", "String", true),
+            ("meta", "std::collections::HashMap<String, i64>", false),
+        ]
+    );
+    assert!(Attachment::CREATE_TABLE_SQL.contains("checksum BLOB NOT NULL"));
+    assert!(Attachment::CREATE_TABLE_SQL.contains("thumbnail BLOB,"));
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Attachment::create_table(&pool).await.unwrap();
+    let mut attachment = Attachment {
+        id: 1,
+        data: vec![1, 2, 3],
+        checksum: [0xde, 0xad, 0xbe, 0xef],
+        thumbnail: None,
+        caption: Some(Some("cover".to_string())),
+        meta: HashMap::from([("pages".to_string(), 412)]),
+    };
+    attachment.insert(&pool).await.unwrap();
+    assert_eq!(Attachment::all_query().fetch_one(&pool).await.unwrap(), attachment);
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "loan")]
+struct Loan {
+    #[naorm_cfg(primary_key)]
+    id: uuid::Uuid,
+    lent_at: chrono::DateTime<chrono::Utc>,
+    due: chrono::NaiveDate,
+    returned_at: Option<chrono::NaiveDateTime>,
+}
+
+mod pg {
+    #[allow(dead_code)]
+    #[derive(naorm::NaormReg, sqlx::FromRow, Debug)]
+    #[naorm_cfg(table_name = "loan", driver = "postgres")]
+    pub struct Loan {
+        #[naorm_cfg(primary_key)]
+        pub id: uuid::Uuid,
+        pub lent_at: chrono::DateTime<chrono::Utc>,
+        pub returned_at: Option<chrono::NaiveDateTime>,
+    }
+}
+
+#[tokio::test]
+async fn test_chrono_and_uuid_columns() {
+    assert_eq!(
+        Loan::CREATE_TABLE_SQL,
+        "CREATE TABLE IF NOT EXISTS loan (\n    id BLOB PRIMARY KEY,\n    lent_at DATETIME NOT NULL,\n    \
+         due DATE NOT NULL,\n    returned_at DATETIME\n)"
+    );
+    assert_eq!(Loan::NAORM_FIELDS[2].default_value, "1970-01-01");
+    assert!(pg::Loan::CREATE_TABLE_SQL.contains("id UUID PRIMARY KEY,\n    lent_at TIMESTAMPTZ NOT NULL,\n    returned_at TIMESTAMP\n"));
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Loan::create_table(&pool).await.unwrap();
+    let lent_at = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let mut loan = Loan {
+        id: uuid::Uuid::from_u128(0x42),
+        lent_at,
+        due: chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+        returned_at: Some(lent_at.naive_utc()),
+    };
+    loan.insert(&pool).await.unwrap();
+    assert_eq!(Loan::all_query().fetch_one(&pool).await.unwrap(), loan);
+    assert!(Loan::verify_schema(&pool).await.unwrap().is_ok());
+}
+
+mod my {
+    // shares its name with `chrono::DateTime`
+    #[derive(sqlx::Type, Debug, Clone, PartialEq)]
+    #[sqlx(transparent)]
+    pub struct DateTime(pub String);
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, PartialEq)]
+#[naorm_cfg(table_name = "event")]
+struct Event {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    at: my::DateTime,
+    logged_at: sqlx::types::chrono::DateTime<chrono::Utc>,
+}
+
+#[tokio::test]
+async fn test_user_type_named_like_a_library_type() {
+    assert!(Event::CREATE_TABLE_SQL.contains("at BLOB NOT NULL,\n    logged_at DATETIME NOT NULL"));
+    assert_eq!(Event::NAORM_FIELDS[1].default_value, "");
+    assert_eq!(Event::NAORM_FIELDS[2].default_value, "1970-01-01T00:00:00Z");
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Event::create_table(&pool).await.unwrap();
+    let mut event = Event {
+        id: 1,
+        at: my::DateTime("noon".to_string()),
+        logged_at: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+    };
+    event.insert(&pool).await.unwrap();
+    assert_eq!(Event::all_query().fetch_one(&pool).await.unwrap(), event);
+}
@@ -0,0 +1,10 @@
+use naorm::NaormEnum;
+
+#[derive(NaormEnum, Debug, Clone, Copy, PartialEq)]
+#[naorm_enum(repr = "int")]
+enum Size {
+    Small = 0,
+    Big = 5_000_000_000,
+}
+
+fn main() {}
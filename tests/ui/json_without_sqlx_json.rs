@@ -0,0 +1,12 @@
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow)]
+#[naorm_cfg(table_name = "reader")]
+struct Reader {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(json)]
+    settings: serde_json::Value,
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use naorm::{NaormColumns, NaormProjection, NaormReg};
+
+#[derive(NaormColumns, Debug, Default)]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(NaormReg, Debug)]
+#[naorm_cfg(table_name = "customer")]
+struct Customer {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(flatten, prefix = "billing_")]
+    billing: Address,
+}
+
+#[derive(NaormProjection, sqlx::FromRow)]
+#[naorm_cfg(model = Customer)]
+struct CustomerBilling {
+    id: i64,
+    billing: Address,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use naorm::NaormReg;
+
+#[derive(NaormReg, sqlx::FromRow)]
+#[naorm_cfg(table_name = "shipment")]
+struct Shipment {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(min = 0.5)]
+    weight: i32,
+    #[naorm_cfg(min = -1, max = 300)]
+    crates: u8,
+}
+
+fn main() {}
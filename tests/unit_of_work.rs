@@ -0,0 +1,178 @@
+use naorm::{Error, NaormReg, UnitOfWork};
+use sqlx::Sqlite;
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "author")]
+struct Author {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(max_len = 20)]
+    name: String,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "article")]
+struct Article {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    #[naorm_cfg(belongs_to = "Author")]
+    author_id: i64,
+    title: String,
+}
+
+async fn setup() -> sqlx::SqlitePool {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    sqlx::query("PRAGMA foreign_keys = ON").execute(&pool).await.unwrap();
+    Author::create_table(&pool).await.unwrap();
+    Article::create_table(&pool).await.unwrap();
+    pool
+}
+
+fn author(id: i64) -> Author {
+    Author { id, name: format!("author {id}") }
+}
+
+fn article(id: i64, author_id: i64) -> Article {
+    Article { id, author_id, title: format!("article {id}") }
+}
+
+#[tokio::test]
+async fn test_unit_of_work_orders_by_foreign_keys() {
+    let pool = setup().await;
+    let mut work = UnitOfWork::<Sqlite>::new();
+    // children registered first are still written after their parents
+    work.insert(article(1, 1)).unwrap();
+    work.insert(article(2, 1)).unwrap();
+    work.insert(author(1)).unwrap();
+    assert_eq!(work.len(), 3);
+    assert_eq!(work.commit(&pool).await.unwrap().rows_affected(), 3);
+
+    let mut work = UnitOfWork::<Sqlite>::new();
+    work.delete(author(1));
+    work.delete(article(1, 1));
+    work.update(Article { title: "moved".to_string(), ..article(2, 2) });
+    work.insert(author(2)).unwrap();
+    work.commit(&pool).await.unwrap();
+    assert_eq!(Article::all_query().fetch_all(&pool).await.unwrap(), vec![Article {
+        title: "moved".to_string(),
+        ..article(2, 2)
+    }]);
+    assert_eq!(Author::all_query().fetch_all(&pool).await.unwrap(), vec![author(2)]);
+
+    // nothing is written when a value is invalid
+    let mut work = UnitOfWork::<Sqlite>::new();
+    work.insert(author(3)).unwrap();
+    work.insert(Author { id: 4, name: "x".repeat(21) }).unwrap();
+    assert!(matches!(work.commit(&pool).await, Err(Error::Validation(_))));
+    assert_eq!(Author::count_query().fetch_one(&pool).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_unit_of_work_savepoints() {
+    let pool = setup().await;
+    let mut tx = pool.begin().await.unwrap();
+    let mut outer = UnitOfWork::<Sqlite>::new();
+    outer.insert(author(1)).unwrap();
+    outer.commit(&mut *tx).await.unwrap();
+
+    let mut nested = UnitOfWork::<Sqlite>::new();
+    nested.insert(article(1, 1)).unwrap();
+    nested.commit(&mut tx).await.unwrap();
+
+    // the failing nested work rolls back to its savepoint only
+    let mut failing = UnitOfWork::<Sqlite>::new();
+    failing.insert(article(2, 1)).unwrap();
+    failing.insert(author(1)).unwrap();
+    assert!(matches!(failing.commit(&mut tx).await, Err(Error::Database(_))));
+    tx.commit().await.unwrap();
+
+    assert_eq!(Author::all_query().fetch_all(&pool).await.unwrap(), vec![author(1)]);
+    assert_eq!(Article::all_query().fetch_all(&pool).await.unwrap(), vec![article(1, 1)]);
+}
+
+// keys assigned before queueing link a parent and its children in one unit
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "shelf")]
+struct Shelf {
+    #[naorm_cfg(primary_key)]
+    id: uuid::Uuid,
+    label: String,
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "shelf_item")]
+struct ShelfItem {
+    #[naorm_cfg(primary_key)]
+    id: uuid::Uuid,
+    #[naorm_cfg(belongs_to = "Shelf")]
+    shelf_id: uuid::Uuid,
+    name: String,
+}
+
+// queued on sqlite, the second of its drivers
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "gauge", drivers = "postgres, sqlite")]
+struct Gauge {
+    #[naorm_cfg(primary_key)]
+    id: i64,
+    level: i32,
+}
+
+#[tokio::test]
+async fn test_unit_of_work_keys_assigned_up_front() {
+    let pool = setup().await;
+    Shelf::create_table(&pool).await.unwrap();
+    ShelfItem::create_table(&pool).await.unwrap();
+    Gauge::create_table(&pool).await.unwrap();
+
+    let shelf = Shelf { id: uuid::Uuid::from_u128(1), label: "fiction".to_string() };
+    let item = ShelfItem { id: uuid::Uuid::from_u128(2), shelf_id: shelf.id, name: "Dune".to_string() };
+    let mut work = UnitOfWork::<Sqlite>::new();
+    work.insert(item.clone()).unwrap();
+    work.insert(shelf.clone()).unwrap();
+    work.insert(Gauge { id: 1, level: 3 }).unwrap();
+    work.commit(&pool).await.unwrap();
+
+    assert_eq!(Shelf::all_query().fetch_all(&pool).await.unwrap(), vec![shelf]);
+    assert_eq!(ShelfItem::all_query().fetch_all(&pool).await.unwrap(), vec![item]);
+    assert_eq!(Gauge::all_query::<Sqlite>().fetch_all(&pool).await.unwrap(), vec![Gauge { id: 1, level: 3 }]);
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug, Clone, PartialEq)]
+#[naorm_cfg(table_name = "memo")]
+struct Memo {
+    #[naorm_cfg(primary_key, auto_increment)]
+    id: i64,
+    body: String,
+}
+
+#[tokio::test]
+async fn test_unit_of_work_rejects_generated_keys() {
+    let mut work = UnitOfWork::<Sqlite>::new();
+    let err = work.insert(Memo { id: 0, body: "draft".to_string() }).unwrap_err();
+    assert!(matches!(err, Error::GeneratedKey("memo")));
+    assert!(work.is_empty());
+}
+
+#[tokio::test]
+async fn test_unit_of_work_runs_deletes_last() {
+    let pool = setup().await;
+    let mut work = UnitOfWork::<Sqlite>::new();
+    work.insert(author(1)).unwrap();
+    work.commit(&pool).await.unwrap();
+
+    // the insert runs before the delete and hits the key still in use
+    let mut work = UnitOfWork::<Sqlite>::new();
+    work.delete(author(1));
+    work.insert(Author { name: "replaced".to_string(), ..author(1) }).unwrap();
+    assert!(matches!(work.commit(&pool).await, Err(Error::Database(_))));
+    assert_eq!(Author::all_query().fetch_all(&pool).await.unwrap(), vec![author(1)]);
+
+    let mut work = UnitOfWork::<Sqlite>::new();
+    work.update(Author { name: "replaced".to_string(), ..author(1) });
+    work.commit(&pool).await.unwrap();
+    assert_eq!(Author::all_query().fetch_all(&pool).await.unwrap(), vec![Author {
+        name: "replaced".to_string(),
+        ..author(1)
+    }]);
+}
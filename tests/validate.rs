@@ -0,0 +1,94 @@
+use naorm::validate::ValidationErrorKind;
+use naorm::NaormReg;
+
+fn no_spaces(v: &str) -> Result<(), String> {
+    if v.contains(' ') {
+        Err("must not contain spaces".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(NaormReg, sqlx::FromRow, Debug)]
+#[naorm_cfg(table_name = "book")]
+struct Book {
+    #[naorm_cfg(primary_key, auto_increment)]
+    id: i64,
+    #[naorm_cfg(max_len = 20, not_empty)]
+    title: String,
+    #[naorm_cfg(min = 1, max = 5000)]
+    pages: i32,
+    #[naorm_cfg(regex = "^#[0-9a-f]{6}$")]
+    color: Option<String>,
+    #[naorm_cfg(validate_with = "no_spaces")]
+    slug: String,
+}
+
+fn book() -> Book {
+    Book {
+        id: 0,
+        title: "Dune".to_string(),
+        pages: 412,
+        color: Some("#aabbcc".to_string()),
+        slug: "dune".to_string(),
+    }
+}
+
+#[test]
+fn test_validate_collects_every_error() {
+    assert!(book().validate().is_ok());
+
+    let mut b = book();
+    b.title = String::new();
+    b.pages = 0;
+    b.color = Some("red".to_string());
+    b.slug = "du ne".to_string();
+    let errors = b.validate().unwrap_err();
+    assert_eq!(errors.errors().len(), 4);
+    assert_eq!(
+        errors.field("title").next().unwrap().kind,
+        ValidationErrorKind::NotEmpty
+    );
+    assert_eq!(
+        errors.field("slug").next().unwrap().kind,
+        ValidationErrorKind::Custom("must not contain spaces".to_string())
+    );
+
+    b = book();
+    b.color = None;
+    b.title = "x".repeat(21);
+    let errors = b.validate().unwrap_err();
+    assert_eq!(
+        errors.errors()[0].kind,
+        ValidationErrorKind::MaxLen { max: 20, actual: 21 }
+    );
+}
+
+#[tokio::test]
+async fn test_insert_validates_before_writing() {
+    assert!(Book::CREATE_TABLE_SQL.contains("title VARCHAR(20) NOT NULL CHECK (length(title) <= 20 AND title <> '')"));
+    assert!(Book::CREATE_TABLE_SQL.contains("pages INTEGER NOT NULL CHECK (pages >= 1 AND pages <= 5000)"));
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    Book::create_table(&pool).await.unwrap();
+
+    let mut b = book();
+    b.insert(&pool).await.unwrap();
+
+    b.pages = -3;
+    match b.insert(&pool).await {
+        Err(naorm::Error::Validation(e)) => assert_eq!(e.errors()[0].field, "pages"),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+    // the CHECK constraint rejects what validate() would have caught
+    assert!(b.insert_query().execute(&pool).await.is_err());
+
+    let rows = Book::all_query().fetch_all(&pool).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].id, 1);
+}
+
+#[test]
+fn test_bounds_fit_the_field_type() {
+    trybuild::TestCases::new().compile_fail("tests/ui/validate_bound_type.rs");
+}
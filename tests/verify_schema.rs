@@ -0,0 +1,52 @@
+use naorm::schema::SchemaIssue;
+use naorm::NaormReg;
+
+#[allow(dead_code)]
+#[derive(NaormReg, sqlx::FromRow, Debug)]
+#[naorm_cfg(table_name = "book")]
+struct Book {
+    #[naorm_cfg(primary_key, auto_increment)]
+    id: i64,
+    #[naorm_cfg(max_len = 40)]
+    title: String,
+    rating: Option<f64>,
+    pages: i64,
+}
+
+#[tokio::test]
+async fn test_verify_schema_reports_drift() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let report = Book::verify_schema(&pool).await.unwrap();
+    assert_eq!(report.issues, vec![SchemaIssue::MissingTable]);
+
+    Book::create_table(&pool).await.unwrap();
+    let report = Book::verify_schema(&pool).await.unwrap();
+    assert!(report.is_ok(), "{}", report);
+
+    sqlx::raw_sql("DROP TABLE book; CREATE TABLE book (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL, rating REAL NOT NULL, isbn TEXT NOT NULL, note TEXT NOT NULL DEFAULT '')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let report = Book::verify_schema(&pool).await.unwrap();
+    assert_eq!(
+        report.issues,
+        vec![
+            SchemaIssue::TypeMismatch {
+                column: "title".to_string(),
+                expected: "VARCHAR(40)".to_string(),
+                actual: "TEXT".to_string(),
+            },
+            SchemaIssue::NullabilityMismatch {
+                column: "rating".to_string(),
+                expected_nullable: true,
+                actual_nullable: false,
+            },
+            SchemaIssue::MissingColumn {
+                column: "pages".to_string()
+            },
+            SchemaIssue::ExtraRequiredColumn {
+                column: "isbn".to_string()
+            },
+        ]
+    );
+}